@@ -0,0 +1,312 @@
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    renderer::Renderer,
+    sampler::SamplerKind,
+    scene::{demo_scene, load_scene, Adaptive, Image},
+    tile::{RenderMode, TileOrder, DEFAULT_TILE_SIZE},
+};
+
+pub const USAGE: &str = "\
+Usage: rust-raytracer render [OPTIONS]
+
+Renders without opening a window and writes the result to disk.
+
+Options:
+    --width <PIXELS>      Image width [default: scene file, else 1920]
+    --height <PIXELS>     Image height [default: scene file, else width * 9 / 16]
+    --samples <N>         Samples per pixel [default: scene file, else 100]
+    --max-depth <N>       Maximum ray bounces [default: scene file, else 50]
+    --roulette-depth <N>  Bounces before Russian roulette may end a path
+                          [default: scene file, else 5]
+    --scene <FILE>        TOML scene description [default: built-in demo scene]
+    --output <FILE>       Output image path; .exr, .hdr and .pfm are written
+                          as linear HDR [default: output/image.png]
+    --extra-channels      Also write first-hit normal and depth channels
+    --tile-size <PIXELS>  Render in square tiles of this size instead of
+                          progressively [default: scene file, else 32 if tiled]
+    --tile-order <ORDER>  Tile order, one of scanline, spiral or hilbert;
+                          implies tiled rendering [default: spiral]
+    --progressive         Render the whole frame one sample at a time, even if
+                          the scene file asks for tiles
+    --threads <N>         Worker threads [default: scene file, else one per CPU]
+    --sampler <NAME>      independent, stratified, halton or sobol
+                          [default: scene file, else sobol]
+    --seed <N>            Random seed; the same seed gives the same image
+                          [default: scene file, else 0]
+    --adaptive-threshold <X>
+                          Stop sampling a pixel once its standard error falls
+                          below this fraction of its brightness
+    --min-samples <N>     Samples every pixel takes before it may stop early;
+                          needs adaptive sampling, from --adaptive-threshold
+                          or the scene file [default: scene file, else 16]
+    --time-limit <SECONDS>
+                          Stop each pixel at its minimum samples once this much
+                          time has passed
+    --heatmap <FILE>      Also save an image of the samples taken per pixel
+    -h, --help            Print this message";
+
+/// Command line settings; anything left unset falls back to the scene file.
+#[derive(Debug, Clone)]
+pub struct RenderArgs {
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    pub samples: Option<u64>,
+    pub max_depth: Option<u64>,
+    pub roulette_depth: Option<u64>,
+    pub scene: Option<String>,
+    pub output: String,
+    pub extra_channels: bool,
+    pub tile_size: Option<u64>,
+    pub tile_order: Option<TileOrder>,
+    pub progressive: bool,
+    pub threads: Option<u64>,
+    pub sampler: Option<SamplerKind>,
+    pub seed: Option<u64>,
+    pub adaptive_threshold: Option<f64>,
+    pub min_samples: Option<u64>,
+    pub time_limit: Option<Duration>,
+    pub heatmap: Option<String>,
+}
+
+impl RenderArgs {
+    /// Parses the arguments following the `render` subcommand. Returns
+    /// `Ok(None)` when help was requested.
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut parsed = Self {
+            width: None,
+            height: None,
+            samples: None,
+            max_depth: None,
+            roulette_depth: None,
+            scene: None,
+            output: String::from("output/image.png"),
+            extra_channels: false,
+            tile_size: None,
+            tile_order: None,
+            progressive: false,
+            threads: None,
+            sampler: None,
+            seed: None,
+            adaptive_threshold: None,
+            min_samples: None,
+            time_limit: None,
+            heatmap: None,
+        };
+
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for '{}'", arg))
+            };
+            match arg.as_str() {
+                "--width" => parsed.width = Some(parse_count(&arg, &value()?)?),
+                "--height" => parsed.height = Some(parse_count(&arg, &value()?)?),
+                "--samples" => parsed.samples = Some(parse_count(&arg, &value()?)?),
+                "--max-depth" => parsed.max_depth = Some(parse_count(&arg, &value()?)?),
+                "--roulette-depth" => parsed.roulette_depth = Some(parse_count(&arg, &value()?)?),
+                "--scene" => parsed.scene = Some(value()?),
+                "--output" => parsed.output = value()?,
+                "--extra-channels" => parsed.extra_channels = true,
+                "--tile-size" => parsed.tile_size = Some(parse_count(&arg, &value()?)?),
+                "--tile-order" => parsed.tile_order = Some(value()?.parse()?),
+                "--progressive" => parsed.progressive = true,
+                "--threads" => parsed.threads = Some(parse_count(&arg, &value()?)?),
+                "--sampler" => parsed.sampler = Some(value()?.parse()?),
+                "--seed" => {
+                    let seed = value()?;
+                    parsed.seed = Some(seed.parse().map_err(|_| {
+                        format!(
+                            "invalid value '{}' for '{}': expected a non-negative integer",
+                            seed, arg
+                        )
+                    })?)
+                }
+                "--adaptive-threshold" => {
+                    parsed.adaptive_threshold = Some(parse_positive(&arg, &value()?)?)
+                }
+                "--min-samples" => parsed.min_samples = Some(parse_count(&arg, &value()?)?),
+                "--time-limit" => {
+                    let seconds = parse_positive(&arg, &value()?)?;
+                    parsed.time_limit = Some(Duration::from_secs_f64(seconds));
+                }
+                "--heatmap" => parsed.heatmap = Some(value()?),
+                "-h" | "--help" => return Ok(None),
+                _ => return Err(format!("unexpected argument '{}'", arg)),
+            }
+        }
+
+        Ok(Some(parsed))
+    }
+
+    /// Applies any resolution, sample, depth or scheduling overrides to `image`.
+    fn apply(&self, image: &mut Image) -> Result<(), String> {
+        if let Some(width) = self.width {
+            image.width = width;
+            if self.height.is_none() {
+                image.height = (width as f64 / image.aspect_ratio) as u64;
+            }
+        }
+        if let Some(height) = self.height {
+            image.height = height;
+        }
+        // Scenes are checked when loaded, so only `--width` can get here.
+        if image.height == 0 {
+            return Err(format!(
+                "'--width {}' is too small for an aspect ratio of {:.3}: the height \
+                 rounds to 0; use a larger width or also pass '--height'",
+                image.width, image.aspect_ratio
+            ));
+        }
+        image.aspect_ratio = image.width as f64 / image.height as f64;
+        if let Some(samples) = self.samples {
+            image.samples = samples;
+        }
+        if let Some(max_depth) = self.max_depth {
+            image.max_depth = max_depth;
+        }
+        if let Some(roulette_depth) = self.roulette_depth {
+            image.roulette_depth = roulette_depth;
+        }
+        image.extra_channels |= self.extra_channels;
+        if self.progressive {
+            image.render_mode = RenderMode::Progressive;
+        } else if self.tile_size.is_some() || self.tile_order.is_some() {
+            let (size, order) = match image.render_mode {
+                RenderMode::Tiled { size, order } => (size, order),
+                RenderMode::Progressive => (DEFAULT_TILE_SIZE, TileOrder::default()),
+            };
+            image.render_mode = RenderMode::Tiled {
+                size: self.tile_size.unwrap_or(size),
+                order: self.tile_order.unwrap_or(order),
+            };
+        }
+        if let Some(threads) = self.threads {
+            image.threads = threads as usize;
+        }
+        if let Some(sampler) = self.sampler {
+            image.sampler = sampler;
+        }
+        if let Some(seed) = self.seed {
+            image.seed = seed;
+        }
+        if let Some(threshold) = self.adaptive_threshold {
+            let adaptive = image.adaptive.get_or_insert(Adaptive::new(threshold));
+            adaptive.threshold = threshold;
+        }
+        if let Some(min_samples) = self.min_samples {
+            let adaptive = image.adaptive.as_mut().ok_or(
+                "'--min-samples' needs adaptive sampling; also pass '--adaptive-threshold' \
+                 or set `adaptive` under [image] in the scene",
+            )?;
+            adaptive.min_samples = min_samples;
+        }
+        if let Some(time_limit) = self.time_limit {
+            image.time_limit = Some(time_limit);
+        }
+        Ok(())
+    }
+}
+
+fn parse_count(flag: &str, value: &str) -> Result<u64, String> {
+    match value.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!(
+            "invalid value '{}' for '{}': expected a positive integer",
+            value, flag
+        )),
+    }
+}
+
+fn parse_positive(flag: &str, value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(x) if x > 0.0 && x.is_finite() => Ok(x),
+        _ => Err(format!(
+            "invalid value '{}' for '{}': expected a positive number",
+            value, flag
+        )),
+    }
+}
+
+/// Entry point for the `render` subcommand.
+pub fn run(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let args = match RenderArgs::parse(args)? {
+        Some(args) => args,
+        None => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+    };
+
+    let (scene, cam, img) = match &args.scene {
+        Some(path) => {
+            let (scene, mut cam, mut img) =
+                load_scene(path).map_err(|e| format!("in scene '{}': {}", path, e))?;
+            args.apply(&mut img)?;
+            // The camera was framed for the scene's own resolution.
+            if args.width.is_some() || args.height.is_some() {
+                cam = cam.with_aspect_ratio(img.aspect_ratio);
+            }
+            (scene, cam, img)
+        }
+        None => {
+            let mut img = Image::new(16.0 / 9.0, 1920, 100, 50);
+            args.apply(&mut img)?;
+            let (scene, cam) = demo_scene(img.aspect_ratio);
+            (scene, cam, img)
+        }
+    };
+
+    let samples = img.samples;
+    let min_samples = img.min_samples();
+    let time_limit = img.time_limit;
+    let render_mode = img.render_mode;
+    let mut renderer = Renderer::new(cam, scene, img);
+
+    let start = Instant::now();
+    match render_mode {
+        RenderMode::Progressive => {
+            for sample in 0..samples {
+                let elapsed = renderer.render();
+                eprint!(
+                    "\rSample {}/{} ({:.2?} per sample, {} pixels active)",
+                    sample + 1,
+                    samples,
+                    elapsed,
+                    renderer.active_pixels()
+                );
+                let out_of_time = time_limit.is_some_and(|limit| start.elapsed() >= limit);
+                if renderer.active_pixels() == 0 || (out_of_time && sample + 1 >= min_samples) {
+                    break;
+                }
+            }
+        }
+        RenderMode::Tiled { size, order } => {
+            renderer.render_tiles(size, order, samples, |finished, total| {
+                eprint!("\rTile {}/{}", finished, total);
+            });
+        }
+    }
+    eprintln!("\nRendered in {:.2?}", start.elapsed());
+
+    if let Some(dir) = Path::new(&args.output).parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("cannot create '{}': {}", dir.display(), e))?;
+    }
+    renderer.set_output_buffer();
+    renderer
+        .save_image(&args.output)
+        .map_err(|e| format!("cannot write '{}': {}", args.output, e))?;
+    eprintln!("Saved {}", args.output);
+    if let Some(heatmap) = &args.heatmap {
+        renderer
+            .save_heatmap(heatmap)
+            .map_err(|e| format!("cannot write '{}': {}", heatmap, e))?;
+        eprintln!("Saved {}", heatmap);
+    }
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, Image as ExrImage, Layer, LayerAttributes,
+    WritableImage,
+};
+use image::{codecs::hdr::HdrEncoder, Rgb};
+
+/// A named group of per-pixel float channels written alongside the colour,
+/// such as surface normals or depth.
+#[derive(Debug, Clone)]
+pub struct ExtraLayer {
+    pub name: &'static str,
+    /// Channel suffixes, e.g. `["X", "Y", "Z"]`; `data` interleaves one value per suffix.
+    pub channels: &'static [&'static str],
+    pub data: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HdrFormat {
+    OpenExr,
+    Radiance,
+    Pfm,
+}
+
+impl HdrFormat {
+    /// Picks a format from the file extension, or `None` for LDR formats.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "exr" => Some(HdrFormat::OpenExr),
+            "hdr" => Some(HdrFormat::Radiance),
+            "pfm" => Some(HdrFormat::Pfm),
+            _ => None,
+        }
+    }
+}
+
+/// Writes linear RGB plus any extra layers. All buffers are stored bottom row
+/// first, as the renderer accumulates them.
+///
+/// OpenEXR keeps everything in one file with channels named `layer.suffix`;
+/// the single-image formats write each extra layer to a sibling file named
+/// `image.layer.ext`.
+pub fn save_hdr(
+    path: &Path,
+    format: HdrFormat,
+    width: usize,
+    height: usize,
+    rgb: &[f32],
+    extra: &[ExtraLayer],
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        HdrFormat::OpenExr => write_exr(path, width, height, rgb, extra),
+        HdrFormat::Radiance | HdrFormat::Pfm => {
+            let write = match format {
+                HdrFormat::Radiance => write_radiance,
+                _ => write_pfm,
+            };
+            write(path, width, height, rgb, 3)?;
+            for layer in extra {
+                write(
+                    &sibling_path(path, layer.name),
+                    width,
+                    height,
+                    &layer.data,
+                    layer.channels.len(),
+                )?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn sibling_path(path: &Path, layer: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.{}.{}", stem, layer, extension))
+}
+
+/// Splits an interleaved bottom-up buffer into one top-down plane per channel.
+fn planes(width: usize, height: usize, data: &[f32], components: usize) -> Vec<Vec<f32>> {
+    (0..components)
+        .map(|c| {
+            (0..height)
+                .rev()
+                .flat_map(|y| (0..width).map(move |x| (y * width + x) * components + c))
+                .map(|i| data[i])
+                .collect()
+        })
+        .collect()
+}
+
+fn write_exr(
+    path: &Path,
+    width: usize,
+    height: usize,
+    rgb: &[f32],
+    extra: &[ExtraLayer],
+) -> Result<(), Box<dyn Error>> {
+    let mut channels = Vec::new();
+    for (name, plane) in ["R", "G", "B"].iter().zip(planes(width, height, rgb, 3)) {
+        channels.push(AnyChannel::new(*name, FlatSamples::F32(plane)));
+    }
+    for layer in extra {
+        let layer_planes = planes(width, height, &layer.data, layer.channels.len());
+        for (suffix, plane) in layer.channels.iter().zip(layer_planes) {
+            let name = format!("{}.{}", layer.name, suffix);
+            channels.push(AnyChannel::new(name.as_str(), FlatSamples::F32(plane)));
+        }
+    }
+
+    let layer = Layer::new(
+        (width, height),
+        LayerAttributes::default(),
+        Encoding::SMALL_LOSSLESS,
+        AnyChannels::sort(channels.into()),
+    );
+    ExrImage::from_layer(layer).write().to_file(path)?;
+    Ok(())
+}
+
+fn write_radiance(
+    path: &Path,
+    width: usize,
+    height: usize,
+    data: &[f32],
+    components: usize,
+) -> Result<(), Box<dyn Error>> {
+    // RGBE has no infinity, so misses in the depth layer saturate instead.
+    let planes = planes(width, height, data, components);
+    let value = |c: usize, i: usize| planes[c][i].min(f32::MAX);
+    let pixels: Vec<Rgb<f32>> = (0..width * height)
+        .map(|i| match components {
+            1 => Rgb([value(0, i); 3]),
+            _ => Rgb([value(0, i), value(1, i), value(2, i)]),
+        })
+        .collect();
+    let writer = BufWriter::new(File::create(path)?);
+    HdrEncoder::new(writer).encode(&pixels, width, height)?;
+    Ok(())
+}
+
+/// Portable float map: `PF` for colour, `Pf` for greyscale. A negative scale
+/// marks little-endian data, and rows run bottom to top like our buffers.
+fn write_pfm(
+    path: &Path,
+    width: usize,
+    height: usize,
+    data: &[f32],
+    components: usize,
+) -> Result<(), Box<dyn Error>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let magic = if components == 1 { "Pf" } else { "PF" };
+    write!(writer, "{}\n{} {}\n-1.0\n", magic, width, height)?;
+    for v in &data[..width * height * components] {
+        writer.write_all(&v.to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use nalgebra::{vector, Vector3};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Vector3<f64>,
+    pub max: Vector3<f64>,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f64>, max: Vector3<f64>) -> Self {
+        Self { min, max }
+    }
+
+    /// An inverted box that any `union` will replace.
+    pub fn empty() -> Self {
+        Self {
+            min: vector![f64::INFINITY, f64::INFINITY, f64::INFINITY],
+            max: vector![f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY],
+        }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    pub fn grow(&self, point: &Vector3<f64>) -> Aabb {
+        Aabb {
+            min: self.min.inf(point),
+            max: self.max.sup(point),
+        }
+    }
+
+    pub fn centroid(&self) -> Vector3<f64> {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        if d[0] < 0.0 || d[1] < 0.0 || d[2] < 0.0 {
+            return 0.0;
+        }
+        2.0 * (d[0] * d[1] + d[1] * d[2] + d[2] * d[0])
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let d = self.max - self.min;
+        if d[0] > d[1] && d[0] > d[2] {
+            0
+        } else if d[1] > d[2] {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Slab test against a precomputed reciprocal direction, so traversal
+    /// only pays for the division once per ray.
+    pub fn hit(
+        &self,
+        origin: &Vector3<f64>,
+        inv_direction: &Vector3<f64>,
+        mut t_min: f64,
+        mut t_max: f64,
+    ) -> bool {
+        for axis in 0..3 {
+            let mut t0 = (self.min[axis] - origin[axis]) * inv_direction[axis];
+            let mut t1 = (self.max[axis] - origin[axis]) * inv_direction[axis];
+            if inv_direction[axis] < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,240 @@
+use crate::hittable::{Aabb, HitRecord, Hittable, HittableList, Object};
+use crate::ray::Ray;
+
+const BUCKET_COUNT: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const TRAVERSAL_COST: f64 = 0.125;
+
+#[derive(Debug, Clone)]
+enum BvhNodeKind {
+    Leaf { first: usize, count: usize },
+    Interior { second_child: usize, axis: usize },
+}
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    bounds: Aabb,
+    kind: BvhNodeKind,
+}
+
+/// A bounding volume hierarchy over a list of objects, built with the
+/// surface area heuristic and stored as a flat, depth-first array of nodes.
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    objects: Vec<Object>,
+    nodes: Vec<BvhNode>,
+    // Objects without a bounding box (e.g. infinite planes) are tested linearly.
+    unbounded: Vec<Object>,
+}
+
+struct BuildItem {
+    object: Object,
+    bounds: Aabb,
+    centroid: nalgebra::Vector3<f64>,
+}
+
+impl Bvh {
+    /// Builds the hierarchy with bounds covering the shutter interval
+    /// `time0..time1`, so moving objects are found at any ray time.
+    pub fn new(list: HittableList, time0: f64, time1: f64) -> Self {
+        let mut items = Vec::with_capacity(list.objects.len());
+        let mut unbounded = Vec::new();
+        for object in list.objects {
+            match object.bounding_box(time0, time1) {
+                Some(bounds) => items.push(BuildItem {
+                    centroid: bounds.centroid(),
+                    object,
+                    bounds,
+                }),
+                None => unbounded.push(object),
+            }
+        }
+
+        let mut nodes = Vec::with_capacity(2 * items.len());
+        if !items.is_empty() {
+            Self::build(&mut items, 0, &mut nodes);
+        }
+
+        Self {
+            objects: items.into_iter().map(|item| item.object).collect(),
+            nodes,
+            unbounded,
+        }
+    }
+
+    /// Recursively builds the subtree for `items`, whose first element sits at
+    /// `offset` in the final object array. Returns the index of the new node.
+    fn build(items: &mut [BuildItem], offset: usize, nodes: &mut Vec<BvhNode>) -> usize {
+        let bounds = items
+            .iter()
+            .fold(Aabb::empty(), |acc, item| acc.union(&item.bounds));
+        let node_index = nodes.len();
+        nodes.push(BvhNode {
+            bounds,
+            kind: BvhNodeKind::Leaf {
+                first: offset,
+                count: items.len(),
+            },
+        });
+
+        if items.len() <= 1 {
+            return node_index;
+        }
+
+        let centroid_bounds = items
+            .iter()
+            .fold(Aabb::empty(), |acc, item| acc.grow(&item.centroid));
+        let axis = centroid_bounds.longest_axis();
+        let extent = centroid_bounds.max[axis] - centroid_bounds.min[axis];
+
+        let mid = if extent <= 0.0 {
+            // All centroids coincide; splitting cannot help.
+            if items.len() <= MAX_LEAF_SIZE {
+                return node_index;
+            }
+            items.len() / 2
+        } else {
+            match Self::sah_split(items, &bounds, &centroid_bounds, axis) {
+                Some(mid) => mid,
+                None => return node_index,
+            }
+        };
+
+        Self::build(&mut items[..mid], offset, nodes);
+        let second_child = Self::build(&mut items[mid..], offset + mid, nodes);
+        nodes[node_index].kind = BvhNodeKind::Interior { second_child, axis };
+        node_index
+    }
+
+    /// Bins centroids along `axis` and partitions `items` at the cheapest
+    /// bucket boundary. Returns `None` when a leaf is cheaper than any split.
+    fn sah_split(
+        items: &mut [BuildItem],
+        bounds: &Aabb,
+        centroid_bounds: &Aabb,
+        axis: usize,
+    ) -> Option<usize> {
+        let min = centroid_bounds.min[axis];
+        let extent = centroid_bounds.max[axis] - min;
+        let bucket_of = |item: &BuildItem| {
+            let b = (BUCKET_COUNT as f64 * (item.centroid[axis] - min) / extent) as usize;
+            b.min(BUCKET_COUNT - 1)
+        };
+
+        let mut counts = [0usize; BUCKET_COUNT];
+        let mut bucket_bounds = [Aabb::empty(); BUCKET_COUNT];
+        for item in items.iter() {
+            let b = bucket_of(item);
+            counts[b] += 1;
+            bucket_bounds[b] = bucket_bounds[b].union(&item.bounds);
+        }
+
+        // Sweep from both ends so each candidate split is costed in O(1).
+        let mut right_area = [0.0; BUCKET_COUNT];
+        let mut right_count = [0usize; BUCKET_COUNT];
+        let mut acc = Aabb::empty();
+        let mut n = 0;
+        for b in (1..BUCKET_COUNT).rev() {
+            acc = acc.union(&bucket_bounds[b]);
+            n += counts[b];
+            right_area[b] = acc.surface_area();
+            right_count[b] = n;
+        }
+
+        let parent_area = bounds.surface_area().max(f64::EPSILON);
+        let mut best_cost = f64::INFINITY;
+        let mut best_bucket = 0;
+        let mut acc = Aabb::empty();
+        let mut n = 0;
+        for b in 0..BUCKET_COUNT - 1 {
+            acc = acc.union(&bucket_bounds[b]);
+            n += counts[b];
+            if n == 0 || right_count[b + 1] == 0 {
+                continue;
+            }
+            let cost = TRAVERSAL_COST
+                + (n as f64 * acc.surface_area() + right_count[b + 1] as f64 * right_area[b + 1])
+                    / parent_area;
+            if cost < best_cost {
+                best_cost = cost;
+                best_bucket = b;
+            }
+        }
+
+        let leaf_cost = items.len() as f64;
+        if best_cost.is_infinite() || (items.len() <= MAX_LEAF_SIZE && leaf_cost <= best_cost) {
+            return None;
+        }
+
+        let mut mid = 0;
+        for i in 0..items.len() {
+            if bucket_of(&items[i]) <= best_bucket {
+                items.swap(i, mid);
+                mid += 1;
+            }
+        }
+        Some(mid)
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut rec = None;
+        let mut closest_so_far = t_max;
+
+        for object in &self.unbounded {
+            if let Some(hit) = object.hit(ray, t_min, closest_so_far) {
+                closest_so_far = hit.t;
+                rec = Some(hit);
+            }
+        }
+
+        if self.nodes.is_empty() {
+            return rec;
+        }
+
+        let origin = ray.origin();
+        let inv_direction = ray.direction().map(|d| 1.0 / d);
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0);
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node
+                .bounds
+                .hit(&origin, &inv_direction, t_min, closest_so_far)
+            {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Leaf { first, count } => {
+                    for object in &self.objects[first..first + count] {
+                        if let Some(hit) = object.hit(ray, t_min, closest_so_far) {
+                            closest_so_far = hit.t;
+                            rec = Some(hit);
+                        }
+                    }
+                }
+                BvhNodeKind::Interior { second_child, axis } => {
+                    // Visit the child nearer along the split axis first.
+                    if inv_direction[axis] < 0.0 {
+                        stack.push(index + 1);
+                        stack.push(second_child);
+                    } else {
+                        stack.push(second_child);
+                        stack.push(index + 1);
+                    }
+                }
+            }
+        }
+
+        rec
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+        self.nodes.first().map(|node| node.bounds)
+    }
+}
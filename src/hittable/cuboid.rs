@@ -0,0 +1,74 @@
+use crate::hittable::{Aabb, HitRecord, Hittable, Quad};
+use crate::ray::Ray;
+use crate::utility::Random;
+use nalgebra::{vector, Unit, Vector3};
+
+/// An axis-aligned box between two opposite corners, made of six quads
+/// facing outwards.
+#[derive(Debug, Clone)]
+pub struct Cuboid {
+    min: Vector3<f64>,
+    max: Vector3<f64>,
+    faces: Vec<Quad>,
+    pub material_handle: usize,
+}
+
+impl Cuboid {
+    pub fn new(a: Vector3<f64>, b: Vector3<f64>, material_handle: usize) -> Self {
+        let (min, max) = (a.inf(&b), a.sup(&b));
+        let d = max - min;
+        let (dx, dy, dz) = (
+            vector![d.x, 0.0, 0.0],
+            vector![0.0, d.y, 0.0],
+            vector![0.0, 0.0, d.z],
+        );
+        let faces = vec![
+            Quad::new(vector![min.x, min.y, max.z], dx, dy, material_handle), // front
+            Quad::new(vector![max.x, min.y, max.z], -dz, dy, material_handle), // right
+            Quad::new(vector![max.x, min.y, min.z], -dx, dy, material_handle), // back
+            Quad::new(vector![min.x, min.y, min.z], dz, dy, material_handle), // left
+            Quad::new(vector![min.x, max.y, max.z], dx, -dz, material_handle), // top
+            Quad::new(vector![min.x, min.y, min.z], dx, dz, material_handle), // bottom
+        ];
+        Self {
+            min,
+            max,
+            faces,
+            material_handle,
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut rec = None;
+        let mut closest_so_far = t_max;
+        for face in &self.faces {
+            if let Some(hit) = face.hit(ray, t_min, closest_so_far) {
+                closest_so_far = hit.t;
+                rec = Some(hit);
+            }
+        }
+        rec
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
+
+    /// Faces are picked with equal chance, so the density is the average of
+    /// theirs, far sides included.
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Unit<Vector3<f64>>) -> f64 {
+        let total: f64 = self
+            .faces
+            .iter()
+            .map(|face| face.pdf_value(origin, direction))
+            .sum();
+        total / self.faces.len() as f64
+    }
+
+    fn random(&self, origin: &Vector3<f64>, rng: &mut Random) -> Unit<Vector3<f64>> {
+        let face = rng.random_index(self.faces.len());
+        self.faces[face].random(origin, rng)
+    }
+}
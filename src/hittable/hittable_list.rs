@@ -1,4 +1,4 @@
-use crate::hittable::{HitRecord, Hittable};
+use crate::hittable::{Aabb, HitRecord, Hittable};
 use crate::ray::Ray;
 
 use super::Object;
@@ -35,4 +35,14 @@ impl Hittable for HittableList {
 
         rec
     }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        let mut objects = self.objects.iter();
+        let first = objects.next()?.bounding_box(time0, time1)?;
+        objects.try_fold(first, |acc, object| {
+            object
+                .bounding_box(time0, time1)
+                .map(|bounds| acc.union(&bounds))
+        })
+    }
 }
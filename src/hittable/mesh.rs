@@ -0,0 +1,162 @@
+use std::{fmt, sync::Arc};
+
+use nalgebra::{vector, Unit, Vector2, Vector3};
+
+use crate::hittable::{Aabb, Bvh, HitRecord, Hittable, HittableList, Object};
+use crate::ray::Ray;
+
+/// Vertex attributes shared by every triangle of a mesh.
+#[derive(Default)]
+pub struct MeshData {
+    pub positions: Vec<Vector3<f64>>,
+    pub normals: Vec<Vector3<f64>>,
+    pub uvs: Vec<Vector2<f64>>,
+}
+
+impl fmt::Debug for MeshData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MeshData")
+            .field("positions", &self.positions.len())
+            .field("normals", &self.normals.len())
+            .field("uvs", &self.uvs.len())
+            .finish()
+    }
+}
+
+/// Indices of one triangle corner into the `MeshData` buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshVertex {
+    pub position: usize,
+    pub normal: Option<usize>,
+    pub uv: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Triangle {
+    pub mesh: Arc<MeshData>,
+    pub vertices: [MeshVertex; 3],
+    pub material_handle: usize,
+}
+
+impl Triangle {
+    fn position(&self, corner: usize) -> Vector3<f64> {
+        self.mesh.positions[self.vertices[corner].position]
+    }
+}
+
+impl Hittable for Triangle {
+    // Möller-Trumbore intersection.
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let p0 = self.position(0);
+        let edge1 = self.position(1) - p0;
+        let edge2 = self.position(2) - p0;
+
+        let pvec = ray.direction().cross(&edge2);
+        let det = edge1.dot(&pvec);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        let tvec = ray.origin() - p0;
+        let b1 = tvec.dot(&pvec) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+        let qvec = tvec.cross(&edge1);
+        let b2 = ray.direction().dot(&qvec) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(&qvec) * inv_det;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let geometric_normal = Unit::new_normalize(edge1.cross(&edge2));
+        let b0 = 1.0 - b1 - b2;
+        let normal = match (
+            self.vertices[0].normal,
+            self.vertices[1].normal,
+            self.vertices[2].normal,
+        ) {
+            (Some(n0), Some(n1), Some(n2)) => {
+                let normals = &self.mesh.normals;
+                let n = b0 * normals[n0] + b1 * normals[n1] + b2 * normals[n2];
+                // Keep the interpolated normal on the same side as the face.
+                if n.dot(&geometric_normal) < 0.0 {
+                    Unit::new_normalize(-n)
+                } else {
+                    Unit::new_normalize(n)
+                }
+            }
+            _ => geometric_normal,
+        };
+        let uv = match (
+            self.vertices[0].uv,
+            self.vertices[1].uv,
+            self.vertices[2].uv,
+        ) {
+            (Some(t0), Some(t1), Some(t2)) => {
+                let uvs = &self.mesh.uvs;
+                let uv = b0 * uvs[t0] + b1 * uvs[t1] + b2 * uvs[t2];
+                (uv[0], uv[1])
+            }
+            _ => (b1, b2),
+        };
+
+        Some(HitRecord::from_ray(
+            ray,
+            ray.at(t),
+            self.material_handle,
+            t,
+            &normal,
+            uv,
+        ))
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        // Pad so triangles lying in an axis plane still have some thickness.
+        let pad = vector![1e-9, 1e-9, 1e-9];
+        let bounds = Aabb::empty()
+            .grow(&self.position(0))
+            .grow(&self.position(1))
+            .grow(&self.position(2));
+        Some(Aabb::new(bounds.min - pad, bounds.max + pad))
+    }
+}
+
+/// A triangle mesh with its own BVH over shared vertex buffers.
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    triangles: Bvh,
+}
+
+impl Mesh {
+    /// Builds a mesh from vertex buffers and `(corners, material_handle)` faces.
+    pub fn new(data: MeshData, faces: Vec<([MeshVertex; 3], usize)>) -> Self {
+        let data = Arc::new(data);
+        let mut list = HittableList::new();
+        for (vertices, material_handle) in faces {
+            list.add(Object::Triangle(Triangle {
+                mesh: Arc::clone(&data),
+                vertices,
+                material_handle,
+            }));
+        }
+        Self {
+            // Triangles never move, so any shutter interval gives the same tree.
+            triangles: Bvh::new(list, 0.0, 0.0),
+        }
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.triangles.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.triangles.bounding_box(time0, time1)
+    }
+}
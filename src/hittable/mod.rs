@@ -1,11 +1,28 @@
+pub mod aabb;
+pub mod bvh;
+pub mod cuboid;
 pub mod hittable_list;
+pub mod mesh;
+pub mod moving_sphere;
+pub mod obj;
+pub mod plane;
+pub mod quad;
 pub mod sphere;
+pub mod transformed;
 
-use crate::ray::Ray;
+use crate::{ray::Ray, utility::Random};
+pub use aabb::Aabb;
+pub use bvh::Bvh;
+pub use cuboid::Cuboid;
 use enum_dispatch::enum_dispatch;
 pub use hittable_list::HittableList;
+pub use mesh::{Mesh, Triangle};
+pub use moving_sphere::MovingSphere;
 use nalgebra::{Unit, Vector3};
+pub use plane::Plane;
+pub use quad::{Disk, Quad};
 pub use sphere::Sphere;
+pub use transformed::Transformed;
 
 #[derive(Debug)]
 pub struct HitRecord {
@@ -13,6 +30,9 @@ pub struct HitRecord {
     pub normal: Unit<Vector3<f64>>,
     pub material_handle: usize,
     pub t: f64,
+    /// Surface coordinates for texture lookup.
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
 }
 
@@ -23,6 +43,7 @@ impl HitRecord {
         material_handle: usize,
         t: f64,
         outward_normal: &Unit<Vector3<f64>>,
+        (u, v): (f64, f64),
     ) -> Self {
         let front_face = outward_normal.dot(&ray.direction()) < -1e-5;
         let normal = if front_face {
@@ -36,6 +57,8 @@ impl HitRecord {
             normal,
             material_handle,
             t,
+            u,
+            v,
             front_face,
         }
     }
@@ -44,11 +67,34 @@ impl HitRecord {
 #[enum_dispatch]
 pub trait Hittable {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    /// Bounds enclosing the object over the shutter interval `time0..time1`;
+    /// `None` for objects with no finite bounds.
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb>;
+
+    /// Solid-angle density with which `random` picks `direction` from `origin`.
+    /// Zero for objects that cannot be sampled as lights.
+    fn pdf_value(&self, _origin: &Vector3<f64>, _direction: &Unit<Vector3<f64>>) -> f64 {
+        0.0
+    }
+
+    /// A direction from `origin` towards the object, for sampling it as a light.
+    fn random(&self, _origin: &Vector3<f64>, _rng: &mut Random) -> Unit<Vector3<f64>> {
+        Vector3::x_axis()
+    }
 }
 
 #[derive(Debug, Clone)]
 #[enum_dispatch(Hittable)]
 pub enum Object {
     Sphere(Sphere),
+    MovingSphere(MovingSphere),
     List(HittableList),
+    Bvh(Bvh),
+    Triangle(Triangle),
+    Mesh(Mesh),
+    Plane(Plane),
+    Quad(Quad),
+    Disk(Disk),
+    Box(Cuboid),
+    Transformed(Transformed),
 }
@@ -0,0 +1,70 @@
+use crate::hittable::{Aabb, HitRecord, Hittable, Sphere};
+use crate::ray::Ray;
+use nalgebra::Vector3;
+
+/// A sphere whose centre moves through a list of `(time, centre)` keyframes,
+/// interpolating linearly between them and holding still outside their range.
+/// Two keyframes give plain linear motion.
+#[derive(Debug, Clone)]
+pub struct MovingSphere {
+    keyframes: Vec<(f64, Vector3<f64>)>,
+    pub radius: f64,
+    pub material_handle: usize,
+}
+
+impl MovingSphere {
+    /// Keyframes are sorted by time; at least one is required.
+    pub fn new(
+        mut keyframes: Vec<(f64, Vector3<f64>)>,
+        radius: f64,
+        material_handle: usize,
+    ) -> Self {
+        assert!(!keyframes.is_empty(), "a moving sphere needs a keyframe");
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            keyframes,
+            radius,
+            material_handle,
+        }
+    }
+
+    pub fn centre(&self, time: f64) -> Vector3<f64> {
+        let next = self.keyframes.partition_point(|(t, _)| *t <= time);
+        if next == 0 {
+            return self.keyframes[0].1;
+        }
+        if next == self.keyframes.len() {
+            return self.keyframes[next - 1].1;
+        }
+        let (t0, c0) = self.keyframes[next - 1];
+        let (t1, c1) = self.keyframes[next];
+        c0 + (c1 - c0) * ((time - t0) / (t1 - t0))
+    }
+
+    fn sphere_at(&self, time: f64) -> Sphere {
+        Sphere {
+            centre: self.centre(time),
+            radius: self.radius,
+            material_handle: self.material_handle,
+        }
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.sphere_at(ray.time()).hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        // Motion is piecewise linear, so the ends of the interval and every
+        // keyframe inside it bound the whole sweep.
+        let mut bounds = self.sphere_at(time0).bounding_box(time0, time0)?;
+        bounds = bounds.union(&self.sphere_at(time1).bounding_box(time1, time1)?);
+        for (t, _) in &self.keyframes {
+            if time0 < *t && *t < time1 {
+                bounds = bounds.union(&self.sphere_at(*t).bounding_box(*t, *t)?);
+            }
+        }
+        Some(bounds)
+    }
+}
@@ -0,0 +1,161 @@
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+use nalgebra::{vector, Vector3};
+
+use super::mesh::{Mesh, MeshData, MeshVertex};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(e) => write!(f, "{}", e),
+            ObjError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+/// Loads a Wavefront OBJ file as a triangle mesh.
+///
+/// Faces after a `usemtl` statement get the handle `materials` maps that name
+/// to; faces with no `usemtl`, or one not in the map, use `default_material`.
+/// Polygons are fan triangulated. Other statements (`mtllib`, groups,
+/// smoothing) are ignored.
+pub fn load_obj(
+    path: impl AsRef<Path>,
+    materials: &HashMap<String, usize>,
+    default_material: usize,
+) -> Result<Mesh, ObjError> {
+    let source = fs::read_to_string(path).map_err(ObjError::Io)?;
+    parse_obj(&source, materials, default_material)
+}
+
+pub fn parse_obj(
+    source: &str,
+    materials: &HashMap<String, usize>,
+    default_material: usize,
+) -> Result<Mesh, ObjError> {
+    let mut data = MeshData::default();
+    let mut faces = Vec::new();
+    let mut material = default_material;
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let err = |message: String| ObjError::Parse {
+            line: line_number,
+            message,
+        };
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => data.positions.push(parse_vec3(&args).map_err(err)?),
+            "vn" => data.normals.push(parse_vec3(&args).map_err(err)?),
+            "vt" => {
+                let uv = parse_floats(&args, 1).map_err(err)?;
+                data.uvs
+                    .push(vector![uv[0], uv.get(1).copied().unwrap_or(0.0)]);
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(err(format!(
+                        "face needs at least 3 vertices, found {}",
+                        args.len()
+                    )));
+                }
+                let corners = args
+                    .iter()
+                    .map(|arg| parse_corner(arg, &data))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(err)?;
+                for k in 1..corners.len() - 1 {
+                    faces.push(([corners[0], corners[k], corners[k + 1]], material));
+                }
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                material = materials.get(&name).copied().unwrap_or(default_material);
+            }
+            _ => {}
+        }
+    }
+
+    if faces.is_empty() {
+        return Err(ObjError::Parse {
+            line: source.lines().count(),
+            message: String::from("file contains no faces"),
+        });
+    }
+    Ok(Mesh::new(data, faces))
+}
+
+fn parse_floats(args: &[&str], min: usize) -> Result<Vec<f64>, String> {
+    if args.len() < min {
+        return Err(format!(
+            "expected at least {} numbers, found {}",
+            min,
+            args.len()
+        ));
+    }
+    args.iter()
+        .map(|a| {
+            a.parse::<f64>()
+                .map_err(|_| format!("invalid number '{}'", a))
+        })
+        .collect()
+}
+
+fn parse_vec3(args: &[&str]) -> Result<Vector3<f64>, String> {
+    let v = parse_floats(args, 3)?;
+    Ok(vector![v[0], v[1], v[2]])
+}
+
+/// Resolves a 1-based (or negative, relative) OBJ index against `len` entries.
+fn resolve_index(token: &str, len: usize, kind: &str) -> Result<usize, String> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| format!("invalid {} index '{}'", kind, token))?;
+    let resolved = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(format!(
+            "{} index {} out of range (have {})",
+            kind, index, len
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+/// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+fn parse_corner(token: &str, data: &MeshData) -> Result<MeshVertex, String> {
+    let mut parts = token.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), data.positions.len(), "vertex")?;
+    let uv = match parts.next() {
+        Some(t) if !t.is_empty() => Some(resolve_index(t, data.uvs.len(), "texture")?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(t) if !t.is_empty() => Some(resolve_index(t, data.normals.len(), "normal")?),
+        _ => None,
+    };
+    Ok(MeshVertex {
+        position,
+        normal,
+        uv,
+    })
+}
@@ -0,0 +1,54 @@
+use crate::hittable::{Aabb, HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::utility::Onb;
+use nalgebra::{Unit, Vector3};
+
+/// An infinite plane through `point`, facing along `normal`. Texture
+/// coordinates are distances across the plane, so textures repeat once per
+/// unit.
+#[derive(Debug, Clone)]
+pub struct Plane {
+    point: Vector3<f64>,
+    normal: Unit<Vector3<f64>>,
+    basis: Onb,
+    pub material_handle: usize,
+}
+
+impl Plane {
+    pub fn new(point: Vector3<f64>, normal: Vector3<f64>, material_handle: usize) -> Self {
+        let normal = Unit::new_normalize(normal);
+        Self {
+            point,
+            basis: Onb::new(&normal),
+            normal,
+            material_handle,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = (self.point - ray.origin()).dot(&self.normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let point = ray.at(t);
+        let offset = point - self.point;
+        Some(HitRecord::from_ray(
+            ray,
+            point,
+            self.material_handle,
+            t,
+            &self.normal,
+            (offset.dot(&self.basis.u), offset.dot(&self.basis.v)),
+        ))
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        None
+    }
+}
@@ -0,0 +1,215 @@
+use std::f64::consts::PI;
+
+use crate::hittable::{Aabb, HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::utility::{sample_uniform_disk, Onb, Random};
+use nalgebra::{vector, Unit, Vector2, Vector3};
+
+/// A parallelogram with one corner at `origin` and sides `u` and `v`, facing
+/// the way `u × v` points. Texture coordinates run from 0 to 1 along each side.
+#[derive(Debug, Clone)]
+pub struct Quad {
+    origin: Vector3<f64>,
+    u: Vector3<f64>,
+    v: Vector3<f64>,
+    normal: Unit<Vector3<f64>>,
+    /// `u × v` over its squared length, which turns a point on the plane into
+    /// its coordinates along `u` and `v`.
+    w: Vector3<f64>,
+    area: f64,
+    pub material_handle: usize,
+}
+
+impl Quad {
+    pub fn new(
+        origin: Vector3<f64>,
+        u: Vector3<f64>,
+        v: Vector3<f64>,
+        material_handle: usize,
+    ) -> Self {
+        let n = u.cross(&v);
+        Self {
+            origin,
+            u,
+            v,
+            normal: Unit::new_normalize(n),
+            w: n / n.norm_squared(),
+            area: n.norm(),
+            material_handle,
+        }
+    }
+
+    /// A rectangle at `offset` along `axis` (0, 1 or 2 for x, y or z),
+    /// spanning `min` to `max` in the other two coordinates taken in cyclic
+    /// order: y and z for x, z and x for y, x and y for z. It faces along
+    /// the positive axis.
+    pub fn axis_aligned(
+        axis: usize,
+        min: Vector2<f64>,
+        max: Vector2<f64>,
+        offset: f64,
+        material_handle: usize,
+    ) -> Self {
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let mut origin = Vector3::zeros();
+        origin[axis] = offset;
+        origin[a] = min.x;
+        origin[b] = min.y;
+        let mut u = Vector3::zeros();
+        u[a] = max.x - min.x;
+        let mut v = Vector3::zeros();
+        v[b] = max.y - min.y;
+        Self::new(origin, u, v, material_handle)
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = (self.origin - ray.origin()).dot(&self.normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let point = ray.at(t);
+        let planar = point - self.origin;
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        Some(HitRecord::from_ray(
+            ray,
+            point,
+            self.material_handle,
+            t,
+            &self.normal,
+            (alpha, beta),
+        ))
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        // Pad so quads lying in an axis plane still have some thickness.
+        let pad = vector![1e-9, 1e-9, 1e-9];
+        let bounds = Aabb::empty()
+            .grow(&self.origin)
+            .grow(&(self.origin + self.u))
+            .grow(&(self.origin + self.v))
+            .grow(&(self.origin + self.u + self.v));
+        Some(Aabb::new(bounds.min - pad, bounds.max + pad))
+    }
+
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Unit<Vector3<f64>>) -> f64 {
+        solid_angle_pdf(self, origin, direction, &self.normal, self.area)
+    }
+
+    /// Picks a point uniformly over the quad's area.
+    fn random(&self, origin: &Vector3<f64>, rng: &mut Random) -> Unit<Vector3<f64>> {
+        let r = rng.sample_2d();
+        let point = self.origin + self.u * r[0] + self.v * r[1];
+        Unit::new_normalize(point - origin)
+    }
+}
+
+/// A flat disk of `radius` around `centre`, facing along `normal`. Texture
+/// coordinates are the angle around the centre, as a fraction of a turn, and
+/// the distance out from it, as a fraction of the radius.
+#[derive(Debug, Clone)]
+pub struct Disk {
+    centre: Vector3<f64>,
+    normal: Unit<Vector3<f64>>,
+    basis: Onb,
+    radius: f64,
+    pub material_handle: usize,
+}
+
+impl Disk {
+    pub fn new(
+        centre: Vector3<f64>,
+        normal: Vector3<f64>,
+        radius: f64,
+        material_handle: usize,
+    ) -> Self {
+        let normal = Unit::new_normalize(normal);
+        Self {
+            centre,
+            basis: Onb::new(&normal),
+            normal,
+            radius,
+            material_handle,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = (self.centre - ray.origin()).dot(&self.normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let point = ray.at(t);
+        let local = self.basis.to_local(&(point - self.centre));
+        let distance = local.x.hypot(local.y);
+        if distance > self.radius {
+            return None;
+        }
+        let angle = local.y.atan2(local.x) / (2.0 * PI);
+        Some(HitRecord::from_ray(
+            ray,
+            point,
+            self.material_handle,
+            t,
+            &self.normal,
+            (angle.rem_euclid(1.0), distance / self.radius),
+        ))
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        // The disk reaches `radius * sin` of its tilt away from each axis.
+        let extent = self
+            .normal
+            .map(|n| self.radius * (1.0 - n * n).max(0.0).sqrt() + 1e-9);
+        Some(Aabb::new(self.centre - extent, self.centre + extent))
+    }
+
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Unit<Vector3<f64>>) -> f64 {
+        let area = PI * self.radius * self.radius;
+        solid_angle_pdf(self, origin, direction, &self.normal, area)
+    }
+
+    /// Picks a point uniformly over the disk's area.
+    fn random(&self, origin: &Vector3<f64>, rng: &mut Random) -> Unit<Vector3<f64>> {
+        let p = sample_uniform_disk(rng.sample_2d()) * self.radius;
+        let point = self.centre + self.basis.u * p.x + self.basis.v * p.y;
+        Unit::new_normalize(point - origin)
+    }
+}
+
+/// Solid-angle density of picking `direction` from `origin` when sampling
+/// points uniformly over a flat `shape` of the given `area`.
+fn solid_angle_pdf(
+    shape: &impl Hittable,
+    origin: &Vector3<f64>,
+    direction: &Unit<Vector3<f64>>,
+    normal: &Unit<Vector3<f64>>,
+    area: f64,
+) -> f64 {
+    let ray = Ray {
+        origin: *origin,
+        direction: *direction,
+        time: 0.0,
+    };
+    match shape.hit(&ray, 0.001, f64::INFINITY) {
+        Some(hit) => {
+            let cosine = direction.dot(normal).abs();
+            hit.t * hit.t / (cosine * area)
+        }
+        None => 0.0,
+    }
+}
@@ -1,6 +1,10 @@
-use crate::hittable::{HitRecord, Hittable};
+use crate::hittable::{Aabb, HitRecord, Hittable};
 use crate::ray::Ray;
-use nalgebra::{Unit, Vector3};
+use crate::utility::{
+    sample_uniform_cone, sample_uniform_sphere, uniform_cone_pdf, uniform_sphere_pdf, Onb, Random,
+};
+use nalgebra::{vector, Unit, Vector3};
+use std::f64::consts::PI;
 use std::ops::Mul;
 
 #[derive(Debug, Clone)]
@@ -10,6 +14,26 @@ pub struct Sphere {
     pub material_handle: usize,
 }
 
+impl Sphere {
+    /// Spherical coordinates of a point on the unit sphere: u is the angle
+    /// around the y axis from x = -1, v runs from y = -1 to y = 1.
+    pub fn uv(p: &Unit<Vector3<f64>>) -> (f64, f64) {
+        let theta = (-p[1]).clamp(-1.0, 1.0).acos();
+        let phi = (-p[2]).atan2(p[0]) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    /// Cosine of the half angle of the cone the sphere subtends from `origin`.
+    fn cos_theta_max(&self, origin: &Vector3<f64>) -> Option<f64> {
+        let distance_squared = (self.centre - origin).norm_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return None;
+        }
+        Some((1.0 - radius_squared / distance_squared).sqrt())
+    }
+}
+
 impl Hittable for Sphere {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
         let oc = ray.origin() - self.centre;
@@ -21,26 +45,54 @@ impl Hittable for Sphere {
             let sqrtd = discriminant.sqrt();
 
             let mut root = -half_b - sqrtd;
-            if t_min < root && root < t_max {
-                return Some(HitRecord::from_ray(
-                    ray,
-                    ray.at(root),
-                    self.material_handle,
-                    root,
-                    &Unit::new_normalize((ray.at(root) - self.centre).mul(1.0 / self.radius)),
-                ));
-            }
-            root = -half_b + sqrtd;
-            if t_min < root && root < t_max {
-                return Some(HitRecord::from_ray(
-                    ray,
-                    ray.at(root),
-                    self.material_handle,
-                    root,
-                    &Unit::new_normalize((ray.at(root) - self.centre).mul(1.0 / self.radius)),
-                ));
+            if !(t_min < root && root < t_max) {
+                root = -half_b + sqrtd;
+                if !(t_min < root && root < t_max) {
+                    return None;
+                }
             }
+            let point = ray.at(root);
+            let outward_normal = Unit::new_normalize((point - self.centre).mul(1.0 / self.radius));
+            return Some(HitRecord::from_ray(
+                ray,
+                point,
+                self.material_handle,
+                root,
+                &outward_normal,
+                Self::uv(&outward_normal),
+            ));
         }
         None
     }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        let r = vector![self.radius, self.radius, self.radius].abs();
+        Some(Aabb::new(self.centre - r, self.centre + r))
+    }
+
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Unit<Vector3<f64>>) -> f64 {
+        let ray = Ray {
+            origin: *origin,
+            direction: *direction,
+            time: 0.0,
+        };
+        if self.hit(&ray, 0.001, f64::INFINITY).is_none() {
+            return 0.0;
+        }
+        match self.cos_theta_max(origin) {
+            Some(cos_theta_max) => uniform_cone_pdf(cos_theta_max),
+            None => uniform_sphere_pdf(),
+        }
+    }
+
+    /// Samples the cone of directions the sphere subtends, uniformly in solid
+    /// angle. From inside, every direction reaches the sphere.
+    fn random(&self, origin: &Vector3<f64>, rng: &mut Random) -> Unit<Vector3<f64>> {
+        let w = Unit::new_normalize(self.centre - origin);
+        let cos_theta_max = match self.cos_theta_max(origin) {
+            Some(cos_theta_max) => cos_theta_max,
+            None => return sample_uniform_sphere(rng.sample_2d()),
+        };
+        Onb::new(&w).to_world(&sample_uniform_cone(rng.sample_2d(), cos_theta_max))
+    }
 }
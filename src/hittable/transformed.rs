@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use crate::hittable::{Aabb, HitRecord, Hittable, Object};
+use crate::ray::Ray;
+use crate::utility::Random;
+use nalgebra::{Matrix3, Matrix4, Point3, Unit, Vector3};
+
+/// An instance of a shared object placed in the world by an affine
+/// transform. Many instances of one mesh share its triangles and BVH, and
+/// only keep a matrix each.
+#[derive(Debug, Clone)]
+pub struct Transformed {
+    object: Arc<Object>,
+    // Boxed so the matrices do not widen every `Object`.
+    transform: Box<Transform>,
+}
+
+#[derive(Debug, Clone)]
+struct Transform {
+    to_world: Matrix4<f64>,
+    to_object: Matrix4<f64>,
+    /// The inverse transpose, which keeps normals perpendicular to the
+    /// transformed surface and on the same side of it.
+    normal_to_world: Matrix3<f64>,
+    /// How much the transform scales volumes.
+    determinant: f64,
+}
+
+impl Transformed {
+    /// Places `object` by `transform`, or fails if the transform cannot be
+    /// inverted.
+    pub fn new(object: Arc<Object>, transform: Matrix4<f64>) -> Result<Self, String> {
+        let to_object = transform
+            .try_inverse()
+            .ok_or_else(|| "the transform cannot be inverted".to_string())?;
+        let linear = transform.fixed_slice::<3, 3>(0, 0);
+        Ok(Self {
+            object,
+            transform: Box::new(Transform {
+                to_world: transform,
+                normal_to_world: to_object.fixed_slice::<3, 3>(0, 0).transpose(),
+                determinant: linear.determinant().abs(),
+                to_object,
+            }),
+        })
+    }
+
+    /// The shared object, in its own space.
+    pub fn object(&self) -> &Object {
+        &self.object
+    }
+
+    /// `origin` and `direction` in object space, with the factor by which
+    /// the transform stretched the direction.
+    fn to_local(
+        &self,
+        origin: &Vector3<f64>,
+        direction: &Vector3<f64>,
+    ) -> (Vector3<f64>, Unit<Vector3<f64>>, f64) {
+        let origin = self
+            .transform
+            .to_object
+            .transform_point(&Point3::from(*origin));
+        let direction = self.transform.to_object.transform_vector(direction);
+        let stretch = direction.norm();
+        (
+            origin.coords,
+            Unit::new_unchecked(direction / stretch),
+            stretch,
+        )
+    }
+}
+
+impl Hittable for Transformed {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // Scaling stretches the ray, so distances along it are rescaled by
+        // the length of the transformed direction.
+        let (origin, direction, stretch) = self.to_local(&ray.origin(), &ray.direction());
+        let local_ray = Ray {
+            origin,
+            direction,
+            time: ray.time(),
+        };
+        let mut hit = self
+            .object
+            .hit(&local_ray, t_min * stretch, t_max * stretch)?;
+
+        hit.point = self
+            .transform
+            .to_world
+            .transform_point(&Point3::from(hit.point))
+            .coords;
+        hit.normal = Unit::new_normalize(self.transform.normal_to_world * hit.normal.into_inner());
+        hit.t /= stretch;
+        Some(hit)
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        let bounds = self.object.bounding_box(time0, time1)?;
+        let corners = (0..8).map(|i| {
+            let corner = Vector3::from_fn(|axis, _| {
+                if i & (1 << axis) == 0 {
+                    bounds.min[axis]
+                } else {
+                    bounds.max[axis]
+                }
+            });
+            self.transform
+                .to_world
+                .transform_point(&Point3::from(corner))
+                .coords
+        });
+        Some(corners.fold(Aabb::empty(), |acc, corner| acc.grow(&corner)))
+    }
+
+    /// A small solid angle around a local direction `w` widens by
+    /// `|det A| / |A w|^3` under the linear part `A` of the transform, and
+    /// `|A w|` is one over the stretch.
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Unit<Vector3<f64>>) -> f64 {
+        let (origin, direction, stretch) = self.to_local(origin, direction);
+        self.object.pdf_value(&origin, &direction) / (stretch.powi(3) * self.transform.determinant)
+    }
+
+    fn random(&self, origin: &Vector3<f64>, rng: &mut Random) -> Unit<Vector3<f64>> {
+        let local_origin = self
+            .transform
+            .to_object
+            .transform_point(&Point3::from(*origin));
+        let direction = self.object.random(&local_origin.coords, rng);
+        Unit::new_normalize(self.transform.to_world.transform_vector(&direction))
+    }
+}
@@ -0,0 +1,140 @@
+use std::str::FromStr;
+
+use nalgebra::{vector, Unit, Vector3};
+
+use super::{
+    microfacet::{fresnel_conductor, Ggx},
+    BsdfSample, Lobe, Material,
+};
+use crate::{
+    hittable::HitRecord,
+    utility::{Onb, Random},
+};
+
+/// A rough metal: GGX microfacets reflecting with the Fresnel term of a
+/// complex index of refraction, so colour and brightness shift towards
+/// grazing angles as on real metals.
+#[derive(Debug, Clone)]
+pub struct Conductor {
+    /// Real part of the index of refraction per channel.
+    pub eta: Vector3<f64>,
+    /// Extinction coefficient per channel.
+    pub k: Vector3<f64>,
+    pub distribution: Ggx,
+}
+
+/// Measured indices of refraction for common metals, at red, green and blue
+/// wavelengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConductorPreset {
+    Gold,
+    Copper,
+    Aluminium,
+}
+
+impl ConductorPreset {
+    /// The `(eta, k)` pair for this metal.
+    pub fn ior(self) -> (Vector3<f64>, Vector3<f64>) {
+        match self {
+            ConductorPreset::Gold => (vector![0.143, 0.374, 1.442], vector![3.983, 2.385, 1.603]),
+            ConductorPreset::Copper => (vector![0.200, 0.924, 1.102], vector![3.912, 2.452, 2.142]),
+            ConductorPreset::Aluminium => {
+                (vector![1.657, 0.880, 0.521], vector![9.224, 6.270, 4.837])
+            }
+        }
+    }
+}
+
+impl FromStr for ConductorPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gold" => Ok(ConductorPreset::Gold),
+            "copper" => Ok(ConductorPreset::Copper),
+            "aluminium" | "aluminum" => Ok(ConductorPreset::Aluminium),
+            _ => Err(format!(
+                "unknown metal '{}' (expected gold, copper or aluminium)",
+                s
+            )),
+        }
+    }
+}
+
+impl Conductor {
+    /// A metal with index of refraction `eta + i k` and perceptual roughness
+    /// `roughness_x` along the surface tangent and `roughness_y` across it.
+    pub fn new(eta: Vector3<f64>, k: Vector3<f64>, roughness_x: f64, roughness_y: f64) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: Ggx::from_roughness(roughness_x, roughness_y),
+        }
+    }
+
+    fn fresnel(&self, cos_i: f64) -> Vector3<f64> {
+        vector![
+            fresnel_conductor(cos_i, self.eta.x, self.k.x),
+            fresnel_conductor(cos_i, self.eta.y, self.k.y),
+            fresnel_conductor(cos_i, self.eta.z, self.k.z)
+        ]
+    }
+}
+
+impl Material for Conductor {
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        rng: &mut Random,
+    ) -> Option<BsdfSample> {
+        let frame = Onb::new(&hit.normal);
+        let wo_local = frame.to_local(wo);
+        let m = self
+            .distribution
+            .sample_visible_normal(&wo_local, rng.sample_2d());
+        let wi_local = 2.0 * wo_local.dot(&m) * m - wo_local;
+        if wo_local.z <= 0.0 || wi_local.z <= 0.0 {
+            return None;
+        }
+
+        // With visible normals sampled, D and most of G cancel against the pdf.
+        let attenuation = self.fresnel(wo_local.dot(&m))
+            * (self.distribution.g(&wo_local, &wi_local) / self.distribution.g1(&wo_local));
+        let wi = frame.to_world(&wi_local);
+        Some(BsdfSample {
+            pdf: Some(self.pdf(hit, wo, &wi)),
+            wi,
+            attenuation,
+            lobe: Lobe::Glossy,
+        })
+    }
+
+    fn eval(
+        &self,
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        wi: &Unit<Vector3<f64>>,
+    ) -> Vector3<f64> {
+        let frame = Onb::new(&hit.normal);
+        let wo = frame.to_local(wo);
+        let wi = frame.to_local(wi);
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return vector![0.0, 0.0, 0.0];
+        }
+        let m = (wo + wi).normalize();
+        self.fresnel(wo.dot(&m))
+            * (self.distribution.d(&m) * self.distribution.g(&wo, &wi) / (4.0 * wo.z))
+    }
+
+    fn pdf(&self, hit: &HitRecord, wo: &Unit<Vector3<f64>>, wi: &Unit<Vector3<f64>>) -> f64 {
+        let frame = Onb::new(&hit.normal);
+        let wo = frame.to_local(wo);
+        let wi = frame.to_local(wi);
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let m = (wo + wi).normalize();
+        self.distribution.visible_normal_pdf(&wo, &m) / (4.0 * wo.dot(&m))
+    }
+}
@@ -1,57 +1,251 @@
-use super::Material;
+use super::{microfacet::Ggx, BsdfSample, Lobe, Material};
 use crate::{
-    material::ScatterRecord,
-    ray::Ray,
-    utility::{reflect, refract},
+    hittable::HitRecord,
+    utility::{reflect, refract, Onb, Random},
 };
-use nalgebra::{vector, Unit};
-use rand::Rng;
+use nalgebra::{vector, Unit, Vector3};
 
 #[derive(Debug, Clone)]
 pub struct Dielectric {
     pub ri: f64,
+    /// Perceptual GGX roughness; zero is perfectly smooth glass, larger
+    /// values frost it.
+    pub roughness: f64,
+    /// Fraction of each channel absorbed per unit distance travelled inside.
+    pub absorption: Vector3<f64>,
+}
+
+impl Dielectric {
+    /// Clear, smooth glass (or water, diamond...) with refractive index `ri`.
+    pub fn new(ri: f64) -> Self {
+        Self {
+            ri,
+            roughness: 0.0,
+            absorption: Vector3::zeros(),
+        }
+    }
+
+    pub fn with_roughness(mut self, roughness: f64) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    /// Tints the medium so that white light has turned `colour` after
+    /// travelling `distance` through it.
+    pub fn with_tint(mut self, colour: Vector3<f64>, distance: f64) -> Self {
+        self.absorption = colour.map(|c| -c.ln() / distance);
+        self
+    }
+
+    fn reflectance(&self, cosine: f64, ri: f64) -> f64 {
+        let mut r0 = (1.0 - ri) / (1.0 + ri);
+        r0 = r0 * r0;
+
+        r0 + (1.0 - r0) * f64::powi(1.0 - cosine, 5)
+    }
+
+    /// Beer-Lambert transmittance of the path to `hit`, which lay inside the
+    /// medium if it arrived at a back face.
+    fn transmittance(&self, hit: &HitRecord) -> Vector3<f64> {
+        if hit.front_face || self.absorption == Vector3::zeros() {
+            return vector![1.0, 1.0, 1.0];
+        }
+        self.absorption.map(|a| (-a * hit.t).exp())
+    }
+
+    /// Relative index of refraction across the surface, from the side `hit`
+    /// was approached from to the other.
+    fn eta(&self, hit: &HitRecord) -> f64 {
+        if hit.front_face {
+            self.ri
+        } else {
+            1.0 / self.ri
+        }
+    }
+
+    fn distribution(&self) -> Ggx {
+        Ggx::from_roughness(self.roughness, self.roughness)
+    }
+
+    /// Walter et al.'s rough dielectric: microfacets picked by visible
+    /// normal, then reflection or refraction chosen by their Fresnel weight.
+    fn sample_rough(
+        &self,
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        rng: &mut Random,
+    ) -> Option<BsdfSample> {
+        let frame = Onb::new(&hit.normal);
+        let wo_local = frame.to_local(wo);
+        let distribution = self.distribution();
+        let eta = self.eta(hit);
+        let m = distribution.sample_visible_normal(&wo_local, rng.sample_2d());
+        let cos_o = wo_local.dot(&m);
+        let reflected = fresnel_dielectric(cos_o, eta) > rng.sample_1d();
+        let (wi_local, attenuation) = if reflected {
+            let wi = reflect(-wo_local, Unit::new_unchecked(m));
+            (
+                wi,
+                distribution.g(&wo_local, &wi) / distribution.g1(&wo_local),
+            )
+        } else {
+            let wi = refract(-wo_local, Unit::new_unchecked(m), 1.0 / eta);
+            // Radiance is compressed into a smaller solid angle on the
+            // denser side, by the square of the relative index.
+            (
+                wi,
+                distribution.g(&wo_local, &wi) / distribution.g1(&wo_local) / (eta * eta),
+            )
+        };
+        if wo_local.z <= 0.0 || (wi_local.z > 0.0) != reflected {
+            return None;
+        }
+
+        let wi = frame.to_world(&wi_local);
+        Some(BsdfSample {
+            pdf: Some(self.pdf(hit, wo, &wi)),
+            wi,
+            attenuation: self.transmittance(hit) * attenuation,
+            lobe: if reflected {
+                Lobe::Glossy
+            } else {
+                Lobe::Transmission
+            },
+        })
+    }
+
+    /// The microfacet normal that reflects or refracts `wo` into `wi`,
+    /// facing the same way as the surface normal.
+    fn half_vector(
+        &self,
+        hit: &HitRecord,
+        wo: &Vector3<f64>,
+        wi: &Vector3<f64>,
+    ) -> Option<Vector3<f64>> {
+        let m = if wi.z > 0.0 {
+            wo + wi
+        } else {
+            wo + wi * self.eta(hit)
+        };
+        let m = m.try_normalize(1e-12)?;
+        let m = if m.z < 0.0 { -m } else { m };
+        // Facets facing away from either direction cannot connect them.
+        if wo.dot(&m) <= 0.0 || wi.dot(&m) * wi.z <= 0.0 {
+            return None;
+        }
+        Some(m)
+    }
 }
 
 impl Material for Dielectric {
-    fn scatter(
+    fn sample(
         &self,
-        ray_in: &crate::ray::Ray,
-        hit: &crate::hittable::HitRecord,
-    ) -> super::ScatterRecord {
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        rng: &mut Random,
+    ) -> Option<BsdfSample> {
+        if self.roughness > 0.0 {
+            return self.sample_rough(hit, wo, rng);
+        }
         let refraction_ratio = if hit.front_face {
             1.0 / self.ri
         } else {
             self.ri
         };
-        let unit_direction = ray_in.direction().normalize();
+        let unit_direction = -wo.into_inner();
         let cos_theta = -hit.normal.dot(&unit_direction).clamp(-1.0, 1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let direction = if cannot_refract
-            || self.reflectance(cos_theta, refraction_ratio)
-                > rand::thread_rng().gen_range(0.0..1.0)
-        {
-            reflect(unit_direction, hit.normal)
+        let reflected =
+            cannot_refract || self.reflectance(cos_theta, refraction_ratio) > rng.sample_1d();
+        // Refracted radiance is scaled by the squared index ratio, as in
+        // `sample_rough`.
+        let (direction, scale) = if reflected {
+            (reflect(unit_direction, hit.normal), 1.0)
         } else {
-            refract(unit_direction, hit.normal, refraction_ratio)
+            (
+                refract(unit_direction, hit.normal, refraction_ratio),
+                refraction_ratio * refraction_ratio,
+            )
         };
 
-        ScatterRecord {
-            ray: Some(Ray {
-                origin: hit.point,
-                direction: Unit::new_normalize(direction),
-            }),
-            attenuation: vector![1.0, 1.0, 1.0],
+        Some(BsdfSample {
+            wi: Unit::new_normalize(direction),
+            attenuation: self.transmittance(hit) * scale,
+            pdf: None,
+            lobe: if reflected {
+                Lobe::Specular
+            } else {
+                Lobe::Transmission
+            },
+        })
+    }
+
+    fn eval(
+        &self,
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        wi: &Unit<Vector3<f64>>,
+    ) -> Vector3<f64> {
+        if self.roughness <= 0.0 {
+            return vector![0.0, 0.0, 0.0];
         }
+        let frame = Onb::new(&hit.normal);
+        let wo = frame.to_local(wo);
+        let wi = frame.to_local(wi);
+        let m = match self.half_vector(hit, &wo, &wi) {
+            Some(m) => m,
+            None => return vector![0.0, 0.0, 0.0],
+        };
+        let distribution = self.distribution();
+        let eta = self.eta(hit);
+        let fresnel = fresnel_dielectric(wo.dot(&m), eta);
+        let value = if wi.z > 0.0 {
+            fresnel * distribution.d(&m) * distribution.g(&wo, &wi) / (4.0 * wo.z)
+        } else {
+            let denominator = (wi.dot(&m) + wo.dot(&m) / eta).powi(2);
+            (1.0 - fresnel)
+                * distribution.d(&m)
+                * distribution.g(&wo, &wi)
+                * (wi.dot(&m) * wo.dot(&m) / (wo.z * denominator)).abs()
+                / (eta * eta)
+        };
+        self.transmittance(hit) * value
     }
-}
 
-impl Dielectric {
-    fn reflectance(&self, cosine: f64, ri: f64) -> f64 {
-        let mut r0 = (1.0 - ri) / (1.0 + ri);
-        r0 = r0 * r0;
+    fn pdf(&self, hit: &HitRecord, wo: &Unit<Vector3<f64>>, wi: &Unit<Vector3<f64>>) -> f64 {
+        if self.roughness <= 0.0 {
+            return 0.0;
+        }
+        let frame = Onb::new(&hit.normal);
+        let wo = frame.to_local(wo);
+        let wi = frame.to_local(wi);
+        let m = match self.half_vector(hit, &wo, &wi) {
+            Some(m) => m,
+            None => return 0.0,
+        };
+        let fresnel = fresnel_dielectric(wo.dot(&m), self.eta(hit));
+        let normal_pdf = self.distribution().visible_normal_pdf(&wo, &m);
+        if wi.z > 0.0 {
+            fresnel * normal_pdf / (4.0 * wo.dot(&m))
+        } else {
+            let eta = self.eta(hit);
+            let denominator = (wi.dot(&m) + wo.dot(&m) / eta).powi(2);
+            (1.0 - fresnel) * normal_pdf * wi.dot(&m).abs() / denominator
+        }
+    }
+}
 
-        r0 + (1.0 - r0) * f64::powi(1.0 - cosine, 5)
+/// Fresnel reflectance for unpolarised light arriving at `cos_i` to the
+/// normal, where `eta` is the index on the far side relative to the near side.
+fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
     }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (parallel * parallel + perpendicular * perpendicular)
 }
@@ -0,0 +1,24 @@
+use nalgebra::{Unit, Vector3};
+
+use super::{BsdfSample, Material};
+use crate::{hittable::HitRecord, utility::Random};
+
+#[derive(Debug, Clone)]
+pub struct DiffuseLight {
+    pub emit: Vector3<f64>,
+}
+
+impl Material for DiffuseLight {
+    fn sample(
+        &self,
+        _hit: &HitRecord,
+        _wo: &Unit<Vector3<f64>>,
+        _rng: &mut Random,
+    ) -> Option<BsdfSample> {
+        None
+    }
+
+    fn emitted(&self, _hit: &HitRecord) -> Vector3<f64> {
+        self.emit
+    }
+}
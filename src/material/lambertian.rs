@@ -1,28 +1,45 @@
 use nalgebra::{Unit, Vector3};
 
-use super::{Material, ScatterRecord};
-use crate::{hittable::HitRecord, ray::Ray, utility, utility::Random};
+use super::{BsdfSample, Lobe, Material};
+use crate::{
+    hittable::HitRecord,
+    texture::{Texture, TextureKind},
+    utility::{cosine_hemisphere_pdf, sample_cosine_hemisphere, Onb, Random},
+};
 
 #[derive(Debug, Clone)]
 pub struct Lambertian {
-    pub albedo: Vector3<f64>,
+    pub albedo: TextureKind,
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray_in: &Ray, hit: &HitRecord) -> ScatterRecord {
-        let mut rng = Random::new();
-        let mut scatter_direction = hit.normal.into_inner() + rng.random_unit_vec().into_inner();
-        if utility::near_zero(&scatter_direction) {
-            scatter_direction = hit.normal.into_inner();
-        }
-        let direction = Unit::new_normalize(scatter_direction);
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        rng: &mut Random,
+    ) -> Option<BsdfSample> {
+        let wi = Onb::new(&hit.normal).to_world(&sample_cosine_hemisphere(rng.sample_2d()));
 
-        ScatterRecord {
-            ray: Some(Ray {
-                origin: hit.point,
-                direction,
-            }),
-            attenuation: self.albedo,
-        }
+        // The cosine and the pdf cancel, so the throughput is just the albedo.
+        Some(BsdfSample {
+            pdf: Some(self.pdf(hit, wo, &wi)),
+            wi,
+            attenuation: self.albedo.value(hit.u, hit.v, &hit.point),
+            lobe: Lobe::Diffuse,
+        })
+    }
+
+    fn eval(
+        &self,
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        wi: &Unit<Vector3<f64>>,
+    ) -> Vector3<f64> {
+        self.albedo.value(hit.u, hit.v, &hit.point) * self.pdf(hit, wo, wi)
+    }
+
+    fn pdf(&self, hit: &HitRecord, _wo: &Unit<Vector3<f64>>, wi: &Unit<Vector3<f64>>) -> f64 {
+        cosine_hemisphere_pdf(hit.normal.dot(wi))
     }
 }
@@ -1,27 +1,88 @@
-use super::{Material, ScatterRecord};
+use std::f64::consts::PI;
+
+use super::{BsdfSample, Lobe, Material};
 use crate::{
-    ray::Ray,
+    hittable::HitRecord,
+    texture::{Texture, TextureKind},
     utility::{self, Random},
 };
-use nalgebra::{Unit, Vector3};
+use nalgebra::{vector, Unit, Vector3};
 
 #[derive(Debug, Clone)]
 pub struct Metal {
-    pub albedo: Vector3<f64>,
+    pub albedo: TextureKind,
     pub fuzz: f64,
 }
 
+impl Metal {
+    /// Density of a fuzzed reflection leaving along `wi`. The sample is the
+    /// direction to a point uniform in a ball of radius `fuzz` around the tip
+    /// of the mirror direction, so this is the ball's volume along `wi`,
+    /// weighted by the squared distance, over its whole volume.
+    fn fuzz_pdf(&self, hit: &HitRecord, wo: &Unit<Vector3<f64>>, wi: &Unit<Vector3<f64>>) -> f64 {
+        let reflected = utility::reflect(-wo.into_inner(), hit.normal);
+        let c = wi.dot(&reflected);
+        let discriminant = c * c - 1.0 + self.fuzz * self.fuzz;
+        if discriminant <= 0.0 {
+            return 0.0;
+        }
+        let far = c + discriminant.sqrt();
+        let near = (c - discriminant.sqrt()).max(0.0);
+        if far <= 0.0 {
+            return 0.0;
+        }
+        (far.powi(3) - near.powi(3)) / (4.0 * PI * self.fuzz.powi(3))
+    }
+}
+
 impl Material for Metal {
-    fn scatter(&self, ray_in: &crate::ray::Ray, hit: &crate::hittable::HitRecord) -> ScatterRecord {
-        let mut rng = Random::new();
-        let reflected = utility::reflect(ray_in.direction().into_inner(), hit.normal);
-        let scattered = Ray {
-            origin: hit.point,
-            direction: Unit::new_normalize(reflected + self.fuzz * rng.random_in_unit_sphere()),
-        };
-        ScatterRecord {
-            ray: Some(scattered),
-            attenuation: self.albedo,
+    /// A mirror reflection nudged by up to `fuzz`. Nudges that would leave
+    /// below the surface are absorbed.
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        rng: &mut Random,
+    ) -> Option<BsdfSample> {
+        let reflected = utility::reflect(-wo.into_inner(), hit.normal);
+        let wi = Unit::new_normalize(reflected + self.fuzz * rng.random_in_unit_sphere());
+        if wi.dot(&hit.normal) <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            attenuation: self.albedo.value(hit.u, hit.v, &hit.point),
+            pdf: if self.fuzz > 0.0 {
+                Some(self.fuzz_pdf(hit, wo, &wi))
+            } else {
+                None
+            },
+            wi,
+            lobe: if self.fuzz > 0.0 {
+                Lobe::Glossy
+            } else {
+                Lobe::Specular
+            },
+        })
+    }
+
+    /// The albedo spread by the fuzz density, so that `sample` weights each
+    /// direction by the albedo alone.
+    fn eval(
+        &self,
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        wi: &Unit<Vector3<f64>>,
+    ) -> Vector3<f64> {
+        if self.fuzz <= 0.0 || wi.dot(&hit.normal) <= 0.0 {
+            return vector![0.0, 0.0, 0.0];
+        }
+        self.albedo.value(hit.u, hit.v, &hit.point) * self.fuzz_pdf(hit, wo, wi)
+    }
+
+    fn pdf(&self, hit: &HitRecord, wo: &Unit<Vector3<f64>>, wi: &Unit<Vector3<f64>>) -> f64 {
+        if self.fuzz <= 0.0 || wi.dot(&hit.normal) <= 0.0 {
+            return 0.0;
         }
+        self.fuzz_pdf(hit, wo, wi)
     }
 }
@@ -0,0 +1,100 @@
+use std::f64::consts::PI;
+
+use nalgebra::{vector, Vector2, Vector3};
+
+/// The GGX (Trowbridge-Reitz) distribution of microfacet normals, with
+/// separate roughness along the tangent and bitangent. All vectors are in the
+/// local shading frame.
+#[derive(Debug, Clone, Copy)]
+pub struct Ggx {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+impl Ggx {
+    /// Perceptual roughness is squared so that it changes the look of a
+    /// surface roughly evenly across its range.
+    pub fn from_roughness(roughness_x: f64, roughness_y: f64) -> Self {
+        let alpha = |r: f64| (r * r).max(1e-4);
+        Self {
+            alpha_x: alpha(roughness_x),
+            alpha_y: alpha(roughness_y),
+        }
+    }
+
+    /// Density of microfacet normal `m`, per unit projected area.
+    pub fn d(&self, m: &Vector3<f64>) -> f64 {
+        if m.z <= 0.0 {
+            return 0.0;
+        }
+        let e = (m.x / self.alpha_x).powi(2) + (m.y / self.alpha_y).powi(2) + m.z * m.z;
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    /// Smith's auxiliary function for direction `w`.
+    fn lambda(&self, w: &Vector3<f64>) -> f64 {
+        let tan2 = ((self.alpha_x * w.x).powi(2) + (self.alpha_y * w.y).powi(2)) / (w.z * w.z);
+        if !tan2.is_finite() {
+            return f64::INFINITY;
+        }
+        0.5 * (-1.0 + (1.0 + tan2).sqrt())
+    }
+
+    /// Fraction of microfacets facing `w` that `w` can see.
+    pub fn g1(&self, w: &Vector3<f64>) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Fraction of microfacets visible from both `wo` and `wi`.
+    pub fn g(&self, wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Samples a microfacet normal in proportion to how much of it `wo`
+    /// sees (Heitz 2018), which wastes no samples on hidden facets.
+    pub fn sample_visible_normal(&self, wo: &Vector3<f64>, u: Vector2<f64>) -> Vector3<f64> {
+        // Stretch to the hemisphere configuration, sample the projected
+        // disk there, then unstretch.
+        let vh = vector![self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z].normalize();
+        let length_squared = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if length_squared > 0.0 {
+            vector![-vh.y, vh.x, 0.0] / length_squared.sqrt()
+        } else {
+            Vector3::x()
+        };
+        let t2 = vh.cross(&t1);
+
+        let r = u[0].sqrt();
+        let phi = 2.0 * PI * u[1];
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        vector![self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)].normalize()
+    }
+
+    /// Density of `sample_visible_normal` picking `m` as seen from `wo`.
+    pub fn visible_normal_pdf(&self, wo: &Vector3<f64>, m: &Vector3<f64>) -> f64 {
+        if wo.z <= 0.0 {
+            return 0.0;
+        }
+        self.g1(wo) * wo.dot(m).max(0.0) * self.d(m) / wo.z
+    }
+}
+
+/// Fresnel reflectance of a conductor with complex index of refraction
+/// `eta + i k`, for unpolarised light arriving at `cos_i` to the normal.
+pub fn fresnel_conductor(cos_i: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_i * cos_i;
+    let sin2 = 1.0 - cos2;
+    let t0 = eta * eta - k * k - sin2;
+    let a2b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let a = (0.5 * (a2b2 + t0)).max(0.0).sqrt();
+    let t1 = a2b2 + cos2;
+    let t2 = 2.0 * cos_i * a;
+    let rs = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    0.5 * (rp + rs)
+}
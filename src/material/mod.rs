@@ -1,24 +1,81 @@
+mod conductor;
 mod dielectric;
+mod diffuse_light;
 mod lambertian;
 mod metal;
+mod microfacet;
+mod principled;
 
+pub use conductor::{Conductor, ConductorPreset};
 pub use dielectric::Dielectric;
+pub use diffuse_light::DiffuseLight;
 use enum_dispatch::enum_dispatch;
 pub use lambertian::Lambertian;
 pub use metal::Metal;
+pub use principled::Principled;
 
 use crate::hittable::HitRecord;
-use crate::ray::Ray;
-use nalgebra::Vector3;
+use crate::utility::Random;
+use nalgebra::{vector, Unit, Vector3};
 
+/// Materials describe scattering with directions in world space: `wo`
+/// points back along the ray that reached the surface and `wi` towards
+/// where the light comes from.
 #[enum_dispatch]
 pub trait Material {
-    fn scatter(&self, ray_in: &Ray, hit: &HitRecord) -> ScatterRecord;
+    /// Picks a direction `wi` for light leaving along `wo`, or `None` if the
+    /// surface absorbs it.
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        rng: &mut Random,
+    ) -> Option<BsdfSample>;
+
+    /// BSDF times the cosine of `wi` to the normal. Delta lobes, which only
+    /// `sample` can produce, contribute nothing here.
+    fn eval(
+        &self,
+        _hit: &HitRecord,
+        _wo: &Unit<Vector3<f64>>,
+        _wi: &Unit<Vector3<f64>>,
+    ) -> Vector3<f64> {
+        vector![0.0, 0.0, 0.0]
+    }
+
+    /// Solid-angle density with which `sample` picks `wi`, leaving out
+    /// delta lobes.
+    fn pdf(&self, _hit: &HitRecord, _wo: &Unit<Vector3<f64>>, _wi: &Unit<Vector3<f64>>) -> f64 {
+        0.0
+    }
+
+    /// Radiance given off by the surface at `hit`; black for anything but lights.
+    fn emitted(&self, _hit: &HitRecord) -> Vector3<f64> {
+        vector![0.0, 0.0, 0.0]
+    }
 }
 
-pub struct ScatterRecord {
-    pub ray: Option<Ray>,
+/// The kind of scattering a sample came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lobe {
+    Diffuse,
+    /// Blurred reflection off a rough surface.
+    Glossy,
+    /// Mirror reflection.
+    Specular,
+    /// Refraction through the surface, smooth or rough.
+    Transmission,
+}
+
+#[derive(Debug, Clone)]
+pub struct BsdfSample {
+    pub wi: Unit<Vector3<f64>>,
+    /// Throughput of the sample: BSDF times cosine over the sampling pdf.
     pub attenuation: Vector3<f64>,
+    /// Solid-angle pdf of `wi`, or `None` for delta lobes and others that
+    /// `eval` and `pdf` do not describe, which light sampling cannot reach.
+    pub pdf: Option<f64>,
+    pub lobe: Lobe,
 }
 
 #[derive(Debug, Clone)]
@@ -26,5 +83,8 @@ pub struct ScatterRecord {
 pub enum MaterialKind {
     Diffuse(Lambertian),
     Metallic(Metal),
+    Conductor(Conductor),
+    Principled(Principled),
     Dielectric(Dielectric),
+    Emissive(DiffuseLight),
 }
@@ -0,0 +1,288 @@
+use std::f64::consts::PI;
+
+use nalgebra::{vector, Unit, Vector3};
+
+use super::{microfacet::Ggx, BsdfSample, Dielectric, Lobe, Material};
+use crate::{
+    hittable::HitRecord,
+    ray::luminance,
+    texture::{Texture, TextureKind},
+    utility::{cosine_hemisphere_pdf, reflect, sample_cosine_hemisphere, Onb, Random},
+};
+
+/// Burley's principled BSDF (Disney 2012/2015): one material whose
+/// parameters, all between 0 and 1 apart from `ior`, blend between plastic,
+/// metal, cloth, lacquered and glassy looks.
+///
+/// It is a weighted sum of lobes: a Burley diffuse (flattened towards a
+/// subsurface look by `subsurface`) with a sheen at grazing angles, a GGX
+/// specular reflection, a rough glass lobe for `transmission`, and a
+/// clearcoat layer on top. Scattering picks one lobe at random and reports
+/// the density of all of them combined, so any lobe can be lit directly.
+#[derive(Debug, Clone)]
+pub struct Principled {
+    pub base_colour: TextureKind,
+    pub metallic: f64,
+    pub roughness: f64,
+    /// Reflectance at normal incidence for non-metals; 0.5 is 4%.
+    pub specular: f64,
+    /// Tints that reflection towards the base colour.
+    pub specular_tint: f64,
+    pub sheen: f64,
+    pub sheen_tint: f64,
+    pub clearcoat: f64,
+    /// Gloss of the clearcoat; 1 is a mirror-like lacquer.
+    pub clearcoat_gloss: f64,
+    /// Fraction of non-metallic light that refracts into the surface, for
+    /// glass and liquids. Transmitted light takes on the base colour.
+    pub transmission: f64,
+    pub ior: f64,
+    pub subsurface: f64,
+}
+
+impl Principled {
+    /// A rough, white-ish dielectric with the reference defaults.
+    pub fn new(base_colour: TextureKind) -> Self {
+        Self {
+            base_colour,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            specular_tint: 0.0,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            clearcoat: 0.0,
+            clearcoat_gloss: 1.0,
+            transmission: 0.0,
+            ior: 1.5,
+            subsurface: 0.0,
+        }
+    }
+
+    /// Sampling probabilities of the diffuse, specular, clearcoat and glass
+    /// lobes, in proportion to their weights in the sum.
+    fn lobe_weights(&self) -> [f64; 4] {
+        let transmission = self.transmission_weight();
+        let weights = [
+            (1.0 - self.metallic) * (1.0 - self.transmission),
+            1.0 - transmission,
+            0.25 * self.clearcoat,
+            transmission,
+        ];
+        let total: f64 = weights.iter().sum();
+        weights.map(|w| w / total)
+    }
+
+    /// Weight of the glass lobe in the sum.
+    fn transmission_weight(&self) -> f64 {
+        (1.0 - self.metallic) * self.transmission
+    }
+
+    /// Whether `hit` is from inside a transmissive surface, where only the
+    /// glass lobe leads back out. Opaque surfaces seen from behind reflect
+    /// like their front, about the flipped normal.
+    fn inside(&self, hit: &HitRecord) -> bool {
+        !hit.front_face && self.transmission_weight() > 0.0
+    }
+
+    fn glass(&self) -> Dielectric {
+        // The glass lobe must stay rough enough to be evaluated.
+        Dielectric::new(self.ior).with_roughness(self.roughness.max(0.01))
+    }
+
+    fn specular_distribution(&self) -> Ggx {
+        Ggx::from_roughness(self.roughness, self.roughness)
+    }
+
+    fn clearcoat_alpha(&self) -> f64 {
+        0.1 * (1.0 - self.clearcoat_gloss) + 0.001 * self.clearcoat_gloss
+    }
+
+    /// Diffuse, sheen, specular and clearcoat reflection, times the cosine.
+    fn reflection(
+        &self,
+        base: &Vector3<f64>,
+        wo: &Vector3<f64>,
+        wi: &Vector3<f64>,
+    ) -> Vector3<f64> {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Vector3::zeros();
+        }
+        let h = (wo + wi).normalize();
+        let cos_d = wi.dot(&h);
+        let base_luminance = luminance(base);
+        let tint = if base_luminance > 0.0 {
+            base / base_luminance
+        } else {
+            vector![1.0, 1.0, 1.0]
+        };
+        let lerp = |a: Vector3<f64>, b: Vector3<f64>, t: f64| a * (1.0 - t) + b * t;
+        let white = vector![1.0, 1.0, 1.0];
+
+        // Burley diffuse with retro-reflection at grazing angles, blended
+        // with a flatter lobe that stands in for light scattered beneath
+        // the surface.
+        let (fl, fv) = (schlick_weight(wi.z), schlick_weight(wo.z));
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let fd = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+        let fss90 = self.roughness * cos_d * cos_d;
+        let fss = (1.0 + (fss90 - 1.0) * fl) * (1.0 + (fss90 - 1.0) * fv);
+        let ss = 1.25 * (fss * (1.0 / (wi.z + wo.z) - 0.5) + 0.5);
+        let diffuse = base * ((fd * (1.0 - self.subsurface) + ss * self.subsurface) / PI);
+        let sheen = lerp(white, tint, self.sheen_tint) * (self.sheen * schlick_weight(cos_d));
+        let diffuse_weight = (1.0 - self.metallic) * (1.0 - self.transmission);
+
+        let specular_f0 = lerp(
+            lerp(white, tint, self.specular_tint) * (0.08 * self.specular),
+            *base,
+            self.metallic,
+        );
+        let fresnel = lerp(specular_f0, white, schlick_weight(cos_d));
+        let distribution = self.specular_distribution();
+        let specular =
+            fresnel * (distribution.d(&h) * distribution.g(wo, wi) / (4.0 * wo.z * wi.z));
+        let specular_weight = 1.0 - (1.0 - self.metallic) * self.transmission;
+
+        let clearcoat = if self.clearcoat > 0.0 {
+            let fresnel = 0.04 + 0.96 * schlick_weight(cos_d);
+            let shadowing = Ggx::from_roughness(0.5, 0.5);
+            0.25 * self.clearcoat
+                * fresnel
+                * gtr1(h.z, self.clearcoat_alpha())
+                * shadowing.g(wo, wi)
+                / (4.0 * wo.z * wi.z)
+        } else {
+            0.0
+        };
+
+        ((diffuse + sheen) * diffuse_weight
+            + specular * specular_weight
+            + vector![clearcoat, clearcoat, clearcoat])
+            * wi.z
+    }
+}
+
+impl Material for Principled {
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        rng: &mut Random,
+    ) -> Option<BsdfSample> {
+        if self.inside(hit) {
+            return self.glass().sample(hit, wo, rng);
+        }
+        let frame = Onb::new(&hit.normal);
+        let wo_local = frame.to_local(wo);
+        let weights = self.lobe_weights();
+        let mut choice = rng.sample_1d();
+        let index = weights
+            .iter()
+            .position(|w| {
+                choice -= w;
+                choice < 0.0
+            })
+            .unwrap_or(0);
+        // Only the reflection lobes draw `u` here; the glass lobe draws its own.
+        let (wi, lobe) = match index {
+            0 => (
+                frame.to_world(&sample_cosine_hemisphere(rng.sample_2d())),
+                Lobe::Diffuse,
+            ),
+            1 => {
+                let m = self
+                    .specular_distribution()
+                    .sample_visible_normal(&wo_local, rng.sample_2d());
+                let wi = frame.to_world(&reflect(-wo_local, Unit::new_unchecked(m)));
+                (wi, Lobe::Glossy)
+            }
+            2 => {
+                let u = rng.sample_2d();
+                let a2 = self.clearcoat_alpha().powi(2);
+                let cos_theta = ((1.0 - a2.powf(1.0 - u[0])) / (1.0 - a2)).max(0.0).sqrt();
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let phi = 2.0 * PI * u[1];
+                let m = vector![sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta];
+                let wi = frame.to_world(&reflect(-wo_local, Unit::new_unchecked(m)));
+                (wi, Lobe::Glossy)
+            }
+            _ => {
+                let glass = self.glass().sample(hit, wo, rng)?;
+                (glass.wi, glass.lobe)
+            }
+        };
+
+        let pdf = self.pdf(hit, wo, &wi);
+        if pdf.is_nan() || pdf <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            attenuation: self.eval(hit, wo, &wi) / pdf,
+            wi,
+            pdf: Some(pdf),
+            lobe,
+        })
+    }
+
+    fn eval(
+        &self,
+        hit: &HitRecord,
+        wo: &Unit<Vector3<f64>>,
+        wi: &Unit<Vector3<f64>>,
+    ) -> Vector3<f64> {
+        if self.inside(hit) {
+            return self.glass().eval(hit, wo, wi);
+        }
+        let frame = Onb::new(&hit.normal);
+        let base = self.base_colour.value(hit.u, hit.v, &hit.point);
+        let transmission = self.transmission_weight();
+        let wi_local = frame.to_local(wi);
+        let mut value = self.reflection(&base, &frame.to_local(wo), &wi_local);
+        if transmission > 0.0 {
+            let glass = self.glass().eval(hit, wo, wi) * transmission;
+            value += if wi_local.z < 0.0 {
+                glass.component_mul(&base)
+            } else {
+                glass
+            };
+        }
+        value
+    }
+
+    fn pdf(&self, hit: &HitRecord, wo: &Unit<Vector3<f64>>, wi: &Unit<Vector3<f64>>) -> f64 {
+        if self.inside(hit) {
+            return self.glass().pdf(hit, wo, wi);
+        }
+        let [diffuse, specular, clearcoat, glass] = self.lobe_weights();
+        let mut pdf = 0.0;
+        if glass > 0.0 {
+            pdf += glass * self.glass().pdf(hit, wo, wi);
+        }
+        let frame = Onb::new(&hit.normal);
+        let wo = frame.to_local(wo);
+        let wi = frame.to_local(wi);
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return pdf;
+        }
+        let h = (wo + wi).normalize();
+        pdf += diffuse * cosine_hemisphere_pdf(wi.z);
+        pdf += specular * self.specular_distribution().visible_normal_pdf(&wo, &h)
+            / (4.0 * wo.dot(&h));
+        if clearcoat > 0.0 {
+            pdf += clearcoat * gtr1(h.z, self.clearcoat_alpha()) * h.z / (4.0 * wo.dot(&h));
+        }
+        pdf
+    }
+}
+
+/// `(1 - cos)^5`, the shape of Schlick's Fresnel approximation.
+fn schlick_weight(cos: f64) -> f64 {
+    (1.0 - cos).clamp(0.0, 1.0).powi(5)
+}
+
+/// The generalised Trowbridge-Reitz distribution with exponent 1, whose long
+/// tail gives the clearcoat its haze.
+fn gtr1(cos_h: f64, alpha: f64) -> f64 {
+    let a2 = alpha * alpha;
+    (a2 - 1.0) / (PI * a2.ln() * (1.0 + (a2 - 1.0) * cos_h * cos_h))
+}
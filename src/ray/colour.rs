@@ -1,6 +1,7 @@
 use crate::utility::*;
 use na::{vector, Vector3};
 use nalgebra as na;
+use serde::Deserialize;
 
 #[allow(dead_code)]
 pub fn vec_to_rgb(c: Vector3<f64>, samples: i64) -> image::Rgb<u8> {
@@ -17,16 +18,49 @@ pub fn vec_to_rgb(c: Vector3<f64>, samples: i64) -> image::Rgb<u8> {
     image::Rgb([ir, ig, ib])
 }
 
-pub fn vec_to_vec3(c: Vector3<f64>, samples: i64) -> Vector3<u64> {
-    let r = c.x();
-    let g = c.y();
-    let b = c.z();
+/// Gamma-corrects (gamma 2) a colour already mapped into 0..1 and quantizes it.
+pub fn gamma_encode(c: Vector3<f64>) -> [u8; 3] {
+    let encode = |v: f64| (256.0 * clamp(v.max(0.0).sqrt(), 0.0, 0.999)) as u8;
+    [encode(c.x()), encode(c.y()), encode(c.z())]
+}
 
-    let scale = 1.0 / samples as f64;
+pub fn luminance(c: &Vector3<f64>) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
 
-    let ir = (256.0 * clamp((r * scale).sqrt(), 0.0, 0.999)) as u64;
-    let ig = (256.0 * clamp((g * scale).sqrt(), 0.0, 0.999)) as u64;
-    let ib = (256.0 * clamp((b * scale).sqrt(), 0.0, 0.999)) as u64;
+/// Maps linear, unbounded radiance into displayable 0..1 values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToneMap {
+    /// Hard clip at 1; matches the look of older renders.
+    #[default]
+    Clamp,
+    /// Luminance-based Reinhard, which keeps hue while compressing highlights.
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve.
+    Aces,
+}
 
-    vector![ir, ig, ib]
+impl ToneMap {
+    pub fn apply(&self, c: Vector3<f64>) -> Vector3<f64> {
+        match self {
+            ToneMap::Clamp => c.map(|v| clamp(v, 0.0, 1.0)),
+            ToneMap::Reinhard => {
+                let l = luminance(&c);
+                if l <= 0.0 {
+                    vector![0.0, 0.0, 0.0]
+                } else {
+                    c * (1.0 / (1.0 + l))
+                }
+            }
+            ToneMap::Aces => c.map(|x| {
+                let x = x.max(0.0);
+                clamp(
+                    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+                    0.0,
+                    1.0,
+                )
+            }),
+        }
+    }
 }
@@ -5,9 +5,12 @@ pub use colour::*;
 use nalgebra::{Unit, Vector3};
 use std::ops::Mul;
 
+#[derive(Debug, Clone)]
 pub struct Ray {
     pub origin: Vector3<f64>,
     pub direction: Unit<Vector3<f64>>,
+    /// Moment within the camera shutter interval the ray was sent.
+    pub time: f64,
 }
 
 impl Ray {
@@ -19,6 +22,10 @@ impl Ray {
         self.direction
     }
 
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
     pub fn at(&self, t: f64) -> Vector3<f64> {
         self.origin + self.direction.mul(t)
     }
@@ -1,19 +1,44 @@
 use image::Rgb;
-use rand::Rng;
-use rayon::prelude::*;
-use std::time::Duration;
+use nalgebra::vector;
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use std::{
+    error::Error,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
 use crate::{
-    ray,
+    export::{self, ExtraLayer, HdrFormat},
+    hittable::Hittable,
+    ray::{self, Ray},
+    sampler::PixelSample,
     scene::{Camera, Image, Scene},
-    utility::{self, *},
+    tile::{self, TileOrder},
+    utility::*,
 };
 
+/// Per-pixel sums: radiance (3), then squared luminance for the variance.
+const PIXEL_STRIDE: usize = 4;
+/// Per-pixel first-hit data: normal (3), depth and hit count.
+const AOV_STRIDE: usize = 5;
+
 #[derive(Debug, Clone)]
 pub struct Renderer {
-    accumulated_buffer: Vec<u64>,
+    /// Sums of linear, unclamped radiance samples per channel and of their
+    /// squared luminance, `PIXEL_STRIDE` values per pixel.
+    accumulated_buffer: Vec<f64>,
+    /// Sums of first-hit normals and depths, present when extra channels are enabled.
+    aov_buffer: Option<Vec<f64>>,
     output_buffer: Vec<u8>,
-    accumulated_samples: usize,
+    /// Samples taken so far for each pixel; tiled rendering fills pixels unevenly.
+    sample_counts: Vec<u32>,
+    /// Pixels the last pass added a sample to.
+    active_pixels: usize,
+    pool: Arc<ThreadPool>,
     camera: Camera,
     image: Image,
     scene: Scene,
@@ -21,14 +46,25 @@ pub struct Renderer {
 
 impl Renderer {
     pub fn new(camera: Camera, scene: Scene, image: Image) -> Self {
-        let accumulated_buffer = vec![0u64; (3 * image.height * image.width) as usize];
-        let output_buffer = vec![0u8; (3 * image.height * image.width) as usize];
-        let accumulated_samples = 0;
+        let pixel_count = (image.height * image.width) as usize;
+        let accumulated_buffer = vec![0.0; PIXEL_STRIDE * pixel_count];
+        let output_buffer = vec![0u8; 3 * pixel_count];
+        let aov_buffer = image
+            .extra_channels
+            .then(|| vec![0.0; AOV_STRIDE * pixel_count]);
+        let sample_counts = vec![0; pixel_count];
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(image.threads)
+            .build()
+            .expect("failed to start render threads");
 
         Self {
             accumulated_buffer,
+            aov_buffer,
             output_buffer,
-            accumulated_samples,
+            sample_counts,
+            active_pixels: pixel_count,
+            pool: Arc::new(pool),
             camera,
             scene,
             image,
@@ -38,80 +74,268 @@ impl Renderer {
     pub fn trace_ray(&self, x: u64, y: u64, max_depth: u64) {
         let u = x as f64 / (self.image.width as f64 - 1.0);
         let v = (self.image.height - y) as f64 / (self.image.height as f64 - 1.0);
-        let ray = self.camera.get_ray(u, v);
-        self.scene.trace_ray(&ray, max_depth);
+        let mut rng = Self::sample_rng(&self.image, y * self.image.width + x, 0);
+        let ray = self.camera.get_ray(u, v, &mut rng);
+        self.scene.trace_ray(&ray, max_depth, &mut rng);
+    }
+
+    fn sample_rng(image: &Image, pixel: u64, index: u32) -> Random {
+        Random::for_sample(
+            image.sampler,
+            PixelSample {
+                seed: image.seed,
+                pixel,
+                index: u64::from(index),
+                count: image.samples,
+            },
+        )
+    }
+
+    fn jittered_ray(camera: &Camera, image: &Image, i: usize, rng: &mut Random) -> Ray {
+        let x = i as u64 % image.width;
+        let y = i as u64 / image.width;
+
+        let jitter = rng.sample_2d();
+        let u = (x as f64 + jitter[0]) / (image.width as f64 - 1.0);
+        let v = (y as f64 + jitter[1]) / (image.height as f64 - 1.0);
+
+        camera.get_ray(u, v, rng)
+    }
+
+    /// Traces sample number `sample` through pixel `i`, adding its radiance
+    /// to `pixel` and its first hit to `aov` if given. The random numbers
+    /// depend only on the seed, pixel and sample number.
+    fn sample_pixel(
+        (camera, image, scene): (&Camera, &Image, &Scene),
+        i: usize,
+        sample: u32,
+        pixel: &mut [f64],
+        aov: Option<&mut [f64]>,
+    ) {
+        let mut rng = Self::sample_rng(image, i as u64, sample);
+        let ray = Self::jittered_ray(camera, image, i, &mut rng);
+        if let Some(aov) = aov {
+            if let Some(hit) = scene.world.hit(&ray, 0.001, f64::INFINITY) {
+                aov[0] += hit.normal.x();
+                aov[1] += hit.normal.y();
+                aov[2] += hit.normal.z();
+                aov[3] += hit.t;
+                aov[4] += 1.0;
+            }
+        }
+        let pixel_colour = scene.ray_colour(&ray, image.max_depth, image.roulette_depth, &mut rng);
+        // A single NaN or infinite sample would poison the pixel forever.
+        if pixel_colour.iter().all(|c| c.is_finite()) {
+            pixel[0] += pixel_colour.x();
+            pixel[1] += pixel_colour.y();
+            pixel[2] += pixel_colour.z();
+            pixel[3] += ray::luminance(&pixel_colour).powi(2);
+        }
     }
 
+    /// Adds one sample to every pixel that has not converged yet.
     pub fn render(&mut self) -> Duration {
         let start = std::time::Instant::now();
-        self.accumulated_samples += 1;
-        self.accumulated_buffer
-            .par_iter_mut()
-            .chunks(3)
-            .enumerate()
-            .for_each(|(i, mut pixel)| {
-                let mut rng = rand::thread_rng();
-                let x = i as u64 % self.image.width;
-                let y = i as u64 / self.image.width;
-
-                let u = (x as f64 + rng.gen::<f64>()) / (self.image.width as f64 - 1.0);
-                let v = (y as f64 + rng.gen::<f64>()) / (self.image.height as f64 - 1.0);
-
-                let ray = self.camera.get_ray(u, v);
-                let pixel_colour = self.scene.ray_colour(&ray, self.image.max_depth);
-                *pixel[0] += ray::vec_to_vec3(pixel_colour, 1).x();
-                *pixel[1] += ray::vec_to_vec3(pixel_colour, 1).y();
-                *pixel[2] += ray::vec_to_vec3(pixel_colour, 1).z();
-            });
+        let context = (&self.camera, &self.image, &self.scene);
+        let adaptive = self.image.adaptive;
+        let sample = |i, pixel: &mut [f64], count: &mut u32, aov| {
+            if adaptive.is_some_and(|adaptive| adaptive.converged(pixel, *count)) {
+                return 0;
+            }
+            Self::sample_pixel(context, i, *count, pixel, aov);
+            *count += 1;
+            1
+        };
+        let pixels = self
+            .accumulated_buffer
+            .par_chunks_mut(PIXEL_STRIDE)
+            .zip(self.sample_counts.par_iter_mut());
+        let aov_buffer = self.aov_buffer.as_mut();
+        self.active_pixels = self.pool.install(|| match aov_buffer {
+            None => pixels
+                .enumerate()
+                .map(|(i, (pixel, count))| sample(i, pixel, count, None))
+                .sum(),
+            Some(aov_buffer) => pixels
+                .zip(aov_buffer.par_chunks_mut(AOV_STRIDE))
+                .enumerate()
+                .map(|(i, ((pixel, count), aov))| sample(i, pixel, count, Some(aov)))
+                .sum(),
+        });
+        start.elapsed()
+    }
+
+    /// Pixels the last call to `render` sampled; zero once every pixel has converged.
+    pub fn active_pixels(&self) -> usize {
+        self.active_pixels
+    }
+
+    /// Renders the image in `size` pixel tiles taken in `order`, adding up to
+    /// `samples` samples to each pixel of a tile before moving on. Pixels
+    /// stop early once converged, or once past the time limit with their
+    /// minimum samples taken. Calls `on_tile` with the number of finished
+    /// tiles and the total after each one.
+    pub fn render_tiles(
+        &mut self,
+        size: u64,
+        order: TileOrder,
+        samples: u64,
+        on_tile: impl Fn(usize, usize) + Sync,
+    ) -> Duration {
+        let start = std::time::Instant::now();
+        let width = self.image.width;
+        let tiles = tile::tiles(width, self.image.height, size, order);
+        let context = (&self.camera, &self.image, &self.scene);
+        let adaptive = self.image.adaptive;
+        let min_samples = self.image.min_samples() as u32;
+        let deadline = self.image.time_limit.map(|limit| start + limit);
+        // Every pixel is in exactly one tile, so its sums can be read from
+        // copies taken before any tile is written back.
+        let previous_sums = self.accumulated_buffer.clone();
+        let previous_counts = self.sample_counts.clone();
+        let film = Mutex::new((
+            &mut self.accumulated_buffer,
+            &mut self.aov_buffer,
+            &mut self.sample_counts,
+        ));
+        let next_tile = AtomicUsize::new(0);
+        let finished = AtomicUsize::new(0);
+
+        // Workers pull tiles off a shared counter rather than splitting the
+        // list up front, so tiles start in the requested order.
+        let workers = self.pool.current_num_threads();
+        self.pool.scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|_| {
+                    while let Some(tile) = tiles.get(next_tile.fetch_add(1, Ordering::Relaxed)) {
+                        let mut sums = Vec::with_capacity(PIXEL_STRIDE * tile.pixel_count());
+                        let mut counts = Vec::with_capacity(tile.pixel_count());
+                        let mut aovs = context
+                            .1
+                            .extra_channels
+                            .then(|| vec![0.0; AOV_STRIDE * tile.pixel_count()]);
+                        for (k, i) in tile.pixels(width).enumerate() {
+                            sums.extend_from_slice(
+                                &previous_sums[PIXEL_STRIDE * i..PIXEL_STRIDE * (i + 1)],
+                            );
+                            let pixel = &mut sums[PIXEL_STRIDE * k..];
+                            let mut count = previous_counts[i];
+                            let target = count + samples as u32;
+                            while count < target {
+                                let converged = adaptive
+                                    .is_some_and(|adaptive| adaptive.converged(pixel, count));
+                                let out_of_time = deadline
+                                    .is_some_and(|deadline| std::time::Instant::now() >= deadline);
+                                if converged || (out_of_time && count >= min_samples) {
+                                    break;
+                                }
+                                let aov = aovs
+                                    .as_mut()
+                                    .map(|aovs| &mut aovs[k * AOV_STRIDE..(k + 1) * AOV_STRIDE]);
+                                Self::sample_pixel(context, i, count, pixel, aov);
+                                count += 1;
+                            }
+                            counts.push(count);
+                        }
+
+                        let mut guard = film.lock().unwrap();
+                        let (accumulated, aov_buffer, sample_counts) = &mut *guard;
+                        for (k, i) in tile.pixels(width).enumerate() {
+                            accumulated[PIXEL_STRIDE * i..PIXEL_STRIDE * (i + 1)]
+                                .copy_from_slice(&sums[PIXEL_STRIDE * k..PIXEL_STRIDE * (k + 1)]);
+                            if let (Some(aov_buffer), Some(aovs)) = (aov_buffer.as_mut(), &aovs) {
+                                for c in 0..AOV_STRIDE {
+                                    aov_buffer[AOV_STRIDE * i + c] += aovs[AOV_STRIDE * k + c];
+                                }
+                            }
+                            sample_counts[i] = counts[k];
+                        }
+                        drop(guard);
+                        on_tile(finished.fetch_add(1, Ordering::Relaxed) + 1, tiles.len());
+                    }
+                });
+            }
+        });
         start.elapsed()
     }
 
+    #[allow(dead_code)]
     pub fn render_to_output_buffer(&mut self) {
         self.output_buffer
             .par_iter_mut()
             .chunks(3)
             .enumerate()
             .for_each(|(i, mut pixel)| {
-                let mut rng = rand::thread_rng();
-                let x = i as u64 % self.image.width;
-                let y = i as u64 / self.image.width;
+                let mut rng = Self::sample_rng(&self.image, i as u64, 0);
+                let ray = Self::jittered_ray(&self.camera, &self.image, i, &mut rng);
+                let pixel_colour = self.scene.ray_colour(
+                    &ray,
+                    self.image.max_depth,
+                    self.image.roulette_depth,
+                    &mut rng,
+                );
 
-                let u = (x as f64 + rng.gen::<f64>()) / (self.image.width as f64 - 1.0);
-                let v = (y as f64 + rng.gen::<f64>()) / (self.image.height as f64 - 1.0);
-
-                let ray = self.camera.get_ray(u, v);
-                let pixel_colour = self.scene.ray_colour(&ray, self.image.max_depth);
-
-                let r = pixel_colour.x();
-                let g = pixel_colour.y();
-                let b = pixel_colour.z();
-
-                *pixel[0] = (256.0 * clamp(r.sqrt(), 0.0, 0.999)) as u8;
-                *pixel[1] = (256.0 * clamp(g.sqrt(), 0.0, 0.999)) as u8;
-                *pixel[2] = (256.0 * clamp(b.sqrt(), 0.0, 0.999)) as u8;
+                let rgb = ray::gamma_encode(self.image.tone_map.apply(pixel_colour));
+                *pixel[0] = rgb[0];
+                *pixel[1] = rgb[1];
+                *pixel[2] = rgb[2];
             });
     }
 
+    /// Tone maps and gamma corrects the averaged radiance into `output_buffer`.
     pub fn set_output_buffer(&mut self) {
+        let tone_map = self.image.tone_map;
         self.output_buffer
             .par_iter_mut()
             .chunks(3)
-            .zip(self.accumulated_buffer.par_iter().chunks(3))
-            .for_each(|(mut pixel, acc)| {
-                let mut scaled = vec![0.0; 3];
-                scaled[0] = *acc[0] as f64 / (self.accumulated_samples as f64);
-                scaled[1] = *acc[1] as f64 / (self.accumulated_samples as f64);
-                scaled[2] = *acc[2] as f64 / (self.accumulated_samples as f64);
-
-                for v in &mut scaled {
-                    utility::clamp(255.0 * v.sqrt(), 0.0, 255.0);
-                }
-                *pixel[0] = scaled[0] as u8;
-                *pixel[1] = scaled[1] as u8;
-                *pixel[2] = scaled[2] as u8;
+            .zip(self.accumulated_buffer.par_chunks(PIXEL_STRIDE))
+            .zip(self.sample_counts.par_iter())
+            .for_each(|((mut pixel, acc), count)| {
+                let scale = 1.0 / (*count).max(1) as f64;
+                let radiance = vector![acc[0], acc[1], acc[2]] * scale;
+                let rgb = ray::gamma_encode(tone_map.apply(radiance));
+                *pixel[0] = rgb[0];
+                *pixel[1] = rgb[1];
+                *pixel[2] = rgb[2];
             });
     }
 
+    /// The averaged linear radiance, three floats per pixel, bottom row first.
+    pub fn get_radiance_buffer(&self) -> Vec<f32> {
+        self.accumulated_buffer
+            .par_chunks(PIXEL_STRIDE)
+            .zip(self.sample_counts.par_iter())
+            .flat_map_iter(|(sum, count)| {
+                let scale = 1.0 / (*count).max(1) as f64;
+                sum[..3].iter().map(move |v| (v * scale) as f32)
+            })
+            .collect()
+    }
+
+    /// Samples taken per pixel as a colour ramp from black (fewest) through
+    /// blue and red to yellow (most), top row first.
+    pub fn get_sample_heatmap(&self) -> image::ImageBuffer<Rgb<u8>, Vec<u8>> {
+        const RAMP: [[f64; 3]; 4] = [
+            [0.0, 0.0, 0.0],
+            [0.1, 0.1, 0.9],
+            [0.9, 0.1, 0.1],
+            [1.0, 1.0, 0.2],
+        ];
+        let max = self.sample_counts.iter().copied().max().unwrap_or(0).max(1);
+        let (width, height) = (self.image.width as u32, self.image.height as u32);
+        image::ImageBuffer::from_fn(width, height, |x, y| {
+            let count = self.sample_counts[((height - 1 - y) * width + x) as usize];
+            let t = f64::from(count) / f64::from(max) * (RAMP.len() - 1) as f64;
+            let k = (t as usize).min(RAMP.len() - 2);
+            let f = t - k as f64;
+            let mut rgb = [0; 3];
+            for c in 0..3 {
+                rgb[c] = ((RAMP[k][c] * (1.0 - f) + RAMP[k + 1][c] * f) * 255.0).round() as u8;
+            }
+            Rgb(rgb)
+        })
+    }
+
     pub fn get_image_buffer(&self) -> Option<image::ImageBuffer<Rgb<u8>, Vec<u8>>> {
         image::ImageBuffer::from_raw(
             self.image.width as u32,
@@ -120,16 +344,73 @@ impl Renderer {
         )
     }
 
+    #[allow(dead_code)]
     pub fn get_raw_image_buffer(&self) -> Vec<u8> {
         self.output_buffer.clone()
     }
 
-    pub fn save_image(&mut self, path: &str) {
+    /// Averaged first-hit normals and depths, and sample counts; pixels that
+    /// never hit anything get a zero normal and infinite depth.
+    pub fn get_extra_layers(&self) -> Vec<ExtraLayer> {
+        let aov_buffer = match &self.aov_buffer {
+            Some(aov_buffer) => aov_buffer,
+            None => return Vec::new(),
+        };
+        let mut normal = Vec::with_capacity(3 * aov_buffer.len() / AOV_STRIDE);
+        let mut depth = Vec::with_capacity(aov_buffer.len() / AOV_STRIDE);
+        let samples = self.sample_counts.iter().map(|c| *c as f32).collect();
+        for (aov, count) in aov_buffer.chunks(AOV_STRIDE).zip(&self.sample_counts) {
+            let scale = 1.0 / (*count).max(1) as f64;
+            normal.extend(aov[..3].iter().map(|n| (n * scale) as f32));
+            depth.push(if aov[4] > 0.0 {
+                (aov[3] / aov[4]) as f32
+            } else {
+                f32::INFINITY
+            });
+        }
+        vec![
+            ExtraLayer {
+                name: "normal",
+                channels: &["X", "Y", "Z"],
+                data: normal,
+            },
+            ExtraLayer {
+                name: "depth",
+                channels: &["Z"],
+                data: depth,
+            },
+            ExtraLayer {
+                name: "samples",
+                channels: &["Y"],
+                data: samples,
+            },
+        ]
+    }
+
+    /// Saves by file extension: `.exr`, `.hdr` and `.pfm` receive the linear
+    /// radiance (plus extra channels when enabled); anything else gets the
+    /// tone mapped 8-bit output buffer.
+    pub fn save_image(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        let path = Path::new(path);
+        if let Some(format) = HdrFormat::from_path(path) {
+            return export::save_hdr(
+                path,
+                format,
+                self.image.width as usize,
+                self.image.height as usize,
+                &self.get_radiance_buffer(),
+                &self.get_extra_layers(),
+            );
+        }
         let img = self
             .get_image_buffer()
             .expect("Accumulated buffer was of incorrect size");
-        image::imageops::flip_vertical(&img)
-            .save(path)
-            .expect("failed to save image");
+        image::imageops::flip_vertical(&img).save(path)?;
+        Ok(())
+    }
+
+    pub fn save_heatmap(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        self.get_sample_heatmap().save(path)?;
+        Ok(())
     }
 }
@@ -0,0 +1,41 @@
+use super::{hash_all, to_unit, Independent, PixelSample, Sampler};
+
+const PRIMES: [u32; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// The Halton sequence: the radical inverse of the sample number in a
+/// different prime base per dimension, randomly shifted per pixel
+/// (Cranley-Patterson rotation). Dimensions past the prime table are
+/// independent random numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Halton;
+
+impl Sampler for Halton {
+    fn sample(&self, sample: &PixelSample, dimension: u32) -> f64 {
+        let base = match PRIMES.get(dimension as usize) {
+            Some(base) => *base,
+            None => return Independent.sample(sample, dimension),
+        };
+        let shift = to_unit(hash_all(&[sample.seed, sample.pixel, u64::from(dimension)]));
+        let x = radical_inverse(base, sample.index) + shift;
+        (x - x.floor()).min(1.0 - f64::EPSILON)
+    }
+}
+
+/// Mirrors the base `base` digits of `index` about the radix point.
+fn radical_inverse(base: u32, mut index: u64) -> f64 {
+    let base = u64::from(base);
+    let inverse = 1.0 / base as f64;
+    let mut scale = inverse;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base) as f64 * scale;
+        index /= base;
+        scale *= inverse;
+    }
+    result
+}
@@ -0,0 +1,16 @@
+use super::{hash_all, to_unit, PixelSample, Sampler};
+
+/// Plain uniform random numbers, with no structure between samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Independent;
+
+impl Sampler for Independent {
+    fn sample(&self, sample: &PixelSample, dimension: u32) -> f64 {
+        to_unit(hash_all(&[
+            sample.seed,
+            sample.pixel,
+            sample.index,
+            u64::from(dimension),
+        ]))
+    }
+}
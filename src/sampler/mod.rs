@@ -0,0 +1,94 @@
+mod halton;
+mod independent;
+mod sobol;
+mod stratified;
+
+use std::str::FromStr;
+
+use enum_dispatch::enum_dispatch;
+pub use halton::Halton;
+pub use independent::Independent;
+pub use sobol::Sobol;
+pub use stratified::Stratified;
+
+/// Dimensions drawn by the camera: pixel position (2), lens position (2)
+/// and shutter time (1).
+pub const CAMERA_DIMENSIONS: u32 = 5;
+/// Dimensions set aside for each bounce: the BSDF sample (up to 4),
+/// choosing a light and the direction towards it (up to 4) and Russian
+/// roulette (1), with room to spare.
+pub const BOUNCE_DIMENSIONS: u32 = 12;
+
+/// First dimension of bounce number `bounce`, so a given decision draws
+/// from the same dimension in every sample of a pixel.
+pub fn bounce_dimension(bounce: u64) -> u32 {
+    let bounce = bounce.min(u64::from(u32::MAX / BOUNCE_DIMENSIONS - 1)) as u32;
+    CAMERA_DIMENSIONS + BOUNCE_DIMENSIONS * bounce
+}
+
+/// Sample number `index` out of `count` taken for pixel `pixel`.
+#[derive(Debug, Clone, Copy)]
+pub struct PixelSample {
+    pub seed: u64,
+    pub pixel: u64,
+    pub index: u64,
+    pub count: u64,
+}
+
+#[enum_dispatch]
+pub trait Sampler {
+    /// Coordinate `dimension` of the sample's point, in `[0, 1)`. The points
+    /// of one pixel cover each dimension more evenly than independent
+    /// random numbers would; different pixels get decorrelated points.
+    fn sample(&self, sample: &PixelSample, dimension: u32) -> f64;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[enum_dispatch(Sampler)]
+pub enum SamplerKind {
+    Independent(Independent),
+    Stratified(Stratified),
+    Halton(Halton),
+    Sobol(Sobol),
+}
+
+impl Default for SamplerKind {
+    fn default() -> Self {
+        SamplerKind::Sobol(Sobol)
+    }
+}
+
+impl FromStr for SamplerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "independent" => Ok(SamplerKind::Independent(Independent)),
+            "stratified" => Ok(SamplerKind::Stratified(Stratified)),
+            "halton" => Ok(SamplerKind::Halton(Halton)),
+            "sobol" => Ok(SamplerKind::Sobol(Sobol)),
+            _ => Err(format!(
+                "unknown sampler '{}' (expected independent, stratified, halton or sobol)",
+                s
+            )),
+        }
+    }
+}
+
+/// SplitMix64 finaliser; spreads nearby inputs far apart.
+pub fn hash(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Hashes several values into one.
+pub fn hash_all(values: &[u64]) -> u64 {
+    values.iter().fold(0, |h, x| hash(h ^ x))
+}
+
+/// Maps hash bits to `[0, 1)`.
+pub fn to_unit(bits: u64) -> f64 {
+    (bits >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
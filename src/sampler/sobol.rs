@@ -0,0 +1,84 @@
+use super::{hash_all, PixelSample, Sampler};
+
+/// Owen-scrambled Sobol points, after Burley's "Practical Hash-based Owen
+/// Scrambling". Dimensions are taken four at a time from a 4D Sobol
+/// sequence; each group of four gets its own shuffle of the sample order,
+/// and every dimension its own nested uniform scramble, so the samples of a
+/// pixel stay well stratified in any single dimension and within each group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sobol;
+
+impl Sampler for Sobol {
+    fn sample(&self, sample: &PixelSample, dimension: u32) -> f64 {
+        let seed = hash_all(&[sample.seed, sample.pixel]);
+        let group_seed = hash_all(&[seed, u64::from(dimension / 4)]) as u32;
+        let index = nested_uniform_scramble(sample.index as u32, group_seed);
+        let x = sobol(index, (dimension % 4) as usize);
+        let x = nested_uniform_scramble(x, hash_all(&[seed, u64::from(dimension)]) as u32);
+        f64::from(x) * (1.0 / 4_294_967_296.0)
+    }
+}
+
+const DIRECTIONS: [[u32; 32]; 4] = directions();
+
+/// Direction numbers for the first four Sobol dimensions. The first is the
+/// van der Corput sequence; the others use the primitive polynomials and
+/// initial values from Joe and Kuo's table as `(degree, coefficients, m)`.
+const fn directions() -> [[u32; 32]; 4] {
+    let polynomials: [(usize, u32, [u32; 3]); 3] =
+        [(1, 0, [1, 0, 0]), (2, 1, [1, 3, 0]), (3, 1, [1, 3, 1])];
+    let mut v = [[0; 32]; 4];
+    let mut i = 0;
+    while i < 32 {
+        v[0][i] = 1 << (31 - i);
+        i += 1;
+    }
+    let mut d = 1;
+    while d < 4 {
+        let (degree, coefficients, m) = polynomials[d - 1];
+        let mut i = 0;
+        while i < 32 {
+            if i < degree {
+                v[d][i] = m[i] << (31 - i);
+            } else {
+                let mut x = v[d][i - degree] ^ (v[d][i - degree] >> degree);
+                let mut k = 1;
+                while k < degree {
+                    x ^= ((coefficients >> (degree - 1 - k)) & 1) * v[d][i - k];
+                    k += 1;
+                }
+                v[d][i] = x;
+            }
+            i += 1;
+        }
+        d += 1;
+    }
+    v
+}
+
+fn sobol(index: u32, dimension: usize) -> u32 {
+    let mut x = 0;
+    let mut bits = index;
+    let mut bit = 0;
+    while bits != 0 {
+        if bits & 1 != 0 {
+            x ^= DIRECTIONS[dimension][bit];
+        }
+        bits >>= 1;
+        bit += 1;
+    }
+    x
+}
+
+/// Owen scrambling of all 32 bits of `x`: a hash permutation applied to
+/// the bit-reversed value, so each bit only depends on the bits above it.
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    let mut x = x.reverse_bits();
+    // Laine-Karras style permutation, with Burley's constants.
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x.reverse_bits()
+}
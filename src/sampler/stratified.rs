@@ -0,0 +1,55 @@
+use super::{hash_all, to_unit, PixelSample, Sampler};
+
+/// Splits every dimension into one stratum per sample and jitters within
+/// it. Strata are shuffled independently per dimension (Latin hypercube
+/// sampling), so any number of samples per pixel stratifies well. Samples
+/// past the pixel's count start a new, differently shuffled round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stratified;
+
+impl Sampler for Stratified {
+    fn sample(&self, sample: &PixelSample, dimension: u32) -> f64 {
+        let count = sample.count.clamp(1, u64::from(u32::MAX));
+        let round = sample.index / count;
+        let key = hash_all(&[sample.seed, sample.pixel, u64::from(dimension), round]);
+        let stratum = permute((sample.index % count) as u32, count as u32, key as u32);
+        let jitter = to_unit(hash_all(&[key, sample.index]));
+        (f64::from(stratum) + jitter) / count as f64
+    }
+}
+
+/// A pseudo-random permutation of `0..len` chosen by `key`, from Kensler's
+/// "Correlated Multi-Jittered Sampling".
+fn permute(mut i: u32, len: u32, key: u32) -> u32 {
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    // Permute within the enclosing power of two until the value lands in range.
+    loop {
+        i ^= key;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= key >> 16;
+        i ^= (i & w) >> 4;
+        i ^= key >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= key >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | key >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < len {
+            break;
+        }
+    }
+    i.wrapping_add(key) % len
+}
@@ -0,0 +1,75 @@
+use std::f64::consts::PI;
+
+use nalgebra::{vector, Vector2};
+
+use crate::utility::{degrees_to_radians, Random};
+
+/// The shape of the lens opening, which sets the shape of out-of-focus
+/// highlights. Samples lie within the unit disk and are scaled by the lens
+/// radius.
+#[derive(Debug, Clone)]
+pub enum ApertureShape {
+    Circle,
+    /// A regular polygon, as formed by a diaphragm with straight blades.
+    Polygon {
+        blades: u32,
+        rotation: f64,
+    },
+    /// An arbitrary simple polygon (convex or not) with vertices within
+    /// [-1, 1] on both axes.
+    Custom {
+        points: Vec<Vector2<f64>>,
+    },
+}
+
+impl ApertureShape {
+    /// Uniformly samples a point on the aperture.
+    pub fn sample(&self, rng: &mut Random) -> Vector2<f64> {
+        match self {
+            ApertureShape::Circle => rng.random_in_unit_disk(),
+            ApertureShape::Polygon { blades, rotation } => {
+                // Each blade spans an equal-area triangle with the centre.
+                // One dimension picks the sector and, rescaled, the position
+                // across it, so the lens only needs two sampler dimensions.
+                let blades = (*blades).max(3) as f64;
+                let u = rng.sample_2d();
+                let x = (u[0] * blades).min(blades - 1e-9);
+                let sector = x.floor();
+                let step = 2.0 * PI / blades;
+                let start = degrees_to_radians(*rotation) + sector * step;
+                let a = vector![start.cos(), start.sin()];
+                let b = vector![(start + step).cos(), (start + step).sin()];
+
+                let su = u[1].sqrt();
+                let v = x - sector;
+                a * (su * (1.0 - v)) + b * (su * v)
+            }
+            ApertureShape::Custom { points } => {
+                for _ in 0..64 {
+                    let p = vector![rng.random_range(-1.0, 1.0), rng.random_range(-1.0, 1.0)];
+                    if contains(points, &p) {
+                        return p;
+                    }
+                }
+                // Degenerate outlines fall back to a pinhole.
+                vector![0.0, 0.0]
+            }
+        }
+    }
+}
+
+/// Even-odd point in polygon test.
+fn contains(points: &[Vector2<f64>], p: &Vector2<f64>) -> bool {
+    let mut inside = false;
+    let mut j = points.len().wrapping_sub(1);
+    for (i, a) in points.iter().enumerate() {
+        let b = &points[j];
+        if (a[1] > p[1]) != (b[1] > p[1])
+            && p[0] < (b[0] - a[0]) * (p[1] - a[1]) / (b[1] - a[1]) + a[0]
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
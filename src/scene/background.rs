@@ -0,0 +1,296 @@
+use std::{error::Error, f64::consts::PI, fmt, fs::File, io::BufReader, path::Path, sync::Arc};
+
+use exr::prelude::read_first_rgba_layer_from_file;
+use image::codecs::hdr::HdrDecoder;
+use nalgebra::{vector, Unit, Vector3};
+
+use super::Sky;
+use crate::{
+    ray::luminance,
+    utility::{degrees_to_radians, Random},
+};
+
+/// Radiance arriving from beyond the scene along rays that hit nothing.
+#[derive(Debug, Clone)]
+pub enum Background {
+    Solid(Vector3<f64>),
+    /// Blends from `bottom` straight down to `top` straight up.
+    Gradient {
+        bottom: Vector3<f64>,
+        top: Vector3<f64>,
+    },
+    Environment(EnvironmentMap),
+    Sky(Sky),
+}
+
+impl Default for Background {
+    /// The white to light blue sky.
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: vector![1.0, 1.0, 1.0],
+            top: vector![0.5, 0.7, 1.0],
+        }
+    }
+}
+
+impl Background {
+    pub fn colour(&self, direction: &Unit<Vector3<f64>>) -> Vector3<f64> {
+        match self {
+            Background::Solid(colour) => *colour,
+            Background::Gradient { bottom, top } => {
+                let t = 0.5 * (direction.y + 1.0);
+                (1.0 - t) * bottom + t * top
+            }
+            Background::Environment(map) => map.colour(direction),
+            Background::Sky(sky) => sky.colour(direction),
+        }
+    }
+
+    /// Whether `sample` can pick directions, so the background can be lit
+    /// from like any other light.
+    pub fn is_sampled(&self) -> bool {
+        match self {
+            Background::Environment(map) => map.is_sampled(),
+            Background::Sky(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Picks a direction with probability roughly proportional to the light
+    /// coming from it. Only meaningful when `is_sampled`.
+    pub fn sample(&self, rng: &mut Random) -> Unit<Vector3<f64>> {
+        match self {
+            Background::Environment(map) => map.sample(rng),
+            Background::Sky(sky) => sky.sample(rng),
+            _ => Vector3::y_axis(),
+        }
+    }
+
+    /// Solid angle density of `sample` choosing `direction`.
+    pub fn pdf(&self, direction: &Unit<Vector3<f64>>) -> f64 {
+        match self {
+            Background::Environment(map) if map.is_sampled() => map.pdf(direction),
+            Background::Sky(sky) => sky.pdf(direction),
+            _ => 0.0,
+        }
+    }
+}
+
+/// An equirectangular (latitude-longitude) HDR image wrapped around the
+/// scene, with the top row straight up.
+#[derive(Clone)]
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    /// Linear RGB texels, top row first.
+    texels: Arc<Vec<Vector3<f64>>>,
+    /// Turn about the vertical axis, in radians.
+    rotation: f64,
+    intensity: f64,
+    /// Cumulative distribution over rows, `height + 1` entries from 0 to 1.
+    marginal: Arc<Vec<f64>>,
+    /// Cumulative distribution along each row, `width + 1` entries per row.
+    conditional: Arc<Vec<f64>>,
+}
+
+impl fmt::Debug for EnvironmentMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnvironmentMap")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("rotation", &self.rotation)
+            .field("intensity", &self.intensity)
+            .finish()
+    }
+}
+
+impl EnvironmentMap {
+    /// Loads a Radiance `.hdr` or OpenEXR `.exr` image. `rotation` turns the
+    /// map about the vertical axis, in degrees, and `intensity` scales it.
+    pub fn open(
+        path: impl AsRef<Path>,
+        rotation: f64,
+        intensity: f64,
+    ) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let (width, height, texels) = match extension.as_deref() {
+            Some("hdr") => {
+                let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+                let metadata = decoder.metadata();
+                let texels = decoder
+                    .read_image_hdr()?
+                    .into_iter()
+                    .map(|p| vector![p[0] as f64, p[1] as f64, p[2] as f64])
+                    .collect();
+                (metadata.width as usize, metadata.height as usize, texels)
+            }
+            Some("exr") => {
+                let image = read_first_rgba_layer_from_file(
+                    path,
+                    |resolution, _| {
+                        (
+                            resolution.width(),
+                            vec![Vector3::zeros(); resolution.width() * resolution.height()],
+                        )
+                    },
+                    |(width, texels): &mut (usize, Vec<Vector3<f64>>),
+                     position,
+                     (r, g, b, _): (f32, f32, f32, f32)| {
+                        texels[position.y() * *width + position.x()] =
+                            vector![r as f64, g as f64, b as f64];
+                    },
+                )?;
+                let size = image.layer_data.size;
+                let (_, texels) = image.layer_data.channel_data.pixels;
+                (size.width(), size.height(), texels)
+            }
+            _ => return Err("expected an .hdr or .exr image".into()),
+        };
+        if width == 0 || height == 0 {
+            return Err("image is empty".into());
+        }
+        Ok(Self::new(width, height, texels, rotation, intensity))
+    }
+
+    /// Builds a map from `width` by `height` linear texels, top row first.
+    pub fn new(
+        width: usize,
+        height: usize,
+        texels: Vec<Vector3<f64>>,
+        rotation: f64,
+        intensity: f64,
+    ) -> Self {
+        // Each texel is picked in proportion to the light it contributes,
+        // which shrinks towards the poles with the solid angle it covers.
+        let mut marginal = Vec::with_capacity(height + 1);
+        let mut conditional = Vec::with_capacity(height * (width + 1));
+        marginal.push(0.0);
+        for y in 0..height {
+            let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+            let start = conditional.len();
+            let mut sum = 0.0;
+            conditional.push(sum);
+            for texel in &texels[y * width..(y + 1) * width] {
+                let weight = luminance(texel) * sin_theta;
+                if weight.is_finite() && weight > 0.0 {
+                    sum += weight;
+                }
+                conditional.push(sum);
+            }
+            let row = &mut conditional[start..];
+            for (x, c) in row.iter_mut().enumerate() {
+                *c = if sum > 0.0 {
+                    *c / sum
+                } else {
+                    x as f64 / width as f64
+                };
+            }
+            marginal.push(marginal[y] + sum);
+        }
+        let total = marginal[height];
+        if total > 0.0 {
+            marginal.iter_mut().for_each(|c| *c /= total);
+        }
+
+        Self {
+            width,
+            height,
+            texels: Arc::new(texels),
+            rotation: degrees_to_radians(rotation),
+            intensity,
+            marginal: Arc::new(marginal),
+            conditional: Arc::new(conditional),
+        }
+    }
+
+    fn is_sampled(&self) -> bool {
+        self.marginal[self.height] > 0.0 && self.intensity > 0.0
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vector3<f64> {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        self.texels[y * self.width + x]
+    }
+
+    /// Map coordinates of `direction`: `u` runs once around the horizon and
+    /// `v` from straight up (0) to straight down (1).
+    fn uv(&self, direction: &Unit<Vector3<f64>>) -> (f64, f64) {
+        let phi = (-direction.z).atan2(direction.x) + PI - self.rotation;
+        let theta = direction.y.clamp(-1.0, 1.0).acos();
+        ((phi / (2.0 * PI)).rem_euclid(1.0), theta / PI)
+    }
+
+    fn direction(&self, u: f64, v: f64) -> Unit<Vector3<f64>> {
+        let phi = 2.0 * PI * u - PI + self.rotation;
+        let (sin_theta, cos_theta) = (PI * v).sin_cos();
+        Unit::new_normalize(vector![
+            phi.cos() * sin_theta,
+            cos_theta,
+            -phi.sin() * sin_theta
+        ])
+    }
+
+    fn colour(&self, direction: &Unit<Vector3<f64>>) -> Vector3<f64> {
+        // Bilinear filtering, with texel centres at half-integer coordinates.
+        let (u, v) = self.uv(direction);
+        let x = u * self.width as f64 - 0.5;
+        let y = v * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+        (top * (1.0 - fy) + bottom * fy) * self.intensity
+    }
+
+    fn sample(&self, rng: &mut Random) -> Unit<Vector3<f64>> {
+        let r = rng.sample_2d();
+        let (y, dy) = sample_cdf(&self.marginal, r[0]);
+        let row = &self.conditional[y * (self.width + 1)..(y + 1) * (self.width + 1)];
+        let (x, dx) = sample_cdf(row, r[1]);
+        self.direction(
+            (x as f64 + dx) / self.width as f64,
+            (y as f64 + dy) / self.height as f64,
+        )
+    }
+
+    fn pdf(&self, direction: &Unit<Vector3<f64>>) -> f64 {
+        let (u, v) = self.uv(direction);
+        let sin_theta = (PI * v).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        let x = ((u * self.width as f64) as usize).min(self.width - 1);
+        let y = ((v * self.height as f64) as usize).min(self.height - 1);
+        let row = &self.conditional[y * (self.width + 1)..];
+        // Density over the unit square of map coordinates, converted to
+        // solid angle; the map spans 2π by π radians.
+        let pdf_uv = (self.marginal[y + 1] - self.marginal[y])
+            * (row[x + 1] - row[x])
+            * (self.width * self.height) as f64;
+        pdf_uv / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+/// Inverts the cumulative distribution `cdf`, returning the segment `u`
+/// falls in and how far along it.
+fn sample_cdf(cdf: &[f64], u: f64) -> (usize, f64) {
+    let segments = cdf.len() - 1;
+    let i = cdf
+        .partition_point(|&c| c <= u)
+        .saturating_sub(1)
+        .min(segments - 1);
+    let width = cdf[i + 1] - cdf[i];
+    let offset = if width > 0.0 {
+        ((u - cdf[i]) / width).clamp(0.0, 1.0)
+    } else {
+        0.5
+    };
+    (i, offset)
+}
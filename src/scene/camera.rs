@@ -1,4 +1,8 @@
-use crate::{ray::Ray, utility::degrees_to_radians};
+use super::ApertureShape;
+use crate::{
+    ray::Ray,
+    utility::{degrees_to_radians, Random},
+};
 use nalgebra::{Unit, Vector3};
 use std::ops::Mul;
 
@@ -8,15 +12,26 @@ pub struct Camera {
     pub horizontal: Vector3<f64>,
     pub vertical: Vector3<f64>,
     pub lower_left_corner: Vector3<f64>,
+    pub u: Vector3<f64>,
+    pub v: Vector3<f64>,
+    pub lens_radius: f64,
+    pub aperture_shape: ApertureShape,
+    pub shutter_open: f64,
+    pub shutter_close: f64,
 }
 
 impl Camera {
+    /// A thin-lens camera. `aperture` is the lens diameter (zero for a
+    /// pinhole) and everything at `focus_dist` from `look_from` is sharp.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         look_from: Vector3<f64>,
         look_at: Vector3<f64>,
         v_up: Vector3<f64>,
         vfov: f64,
         aspect_ratio: f64,
+        aperture: f64,
+        focus_dist: f64,
     ) -> Self {
         let theta = degrees_to_radians(vfov);
         let h = (theta / 2.0).tan();
@@ -28,24 +43,66 @@ impl Camera {
         let v = w.cross(&u);
 
         let origin = look_from;
-        let horizontal = viewport_width * u;
-        let vertical = viewport_height * v;
-        let lower_left_corner = origin - 0.5 * horizontal - 0.5 * vertical - w;
+        let horizontal = focus_dist * viewport_width * u;
+        let vertical = focus_dist * viewport_height * v;
+        let lower_left_corner = origin - 0.5 * horizontal - 0.5 * vertical - focus_dist * w;
         Camera {
             origin,
             horizontal,
             vertical,
             lower_left_corner,
+            u,
+            v,
+            lens_radius: aperture / 2.0,
+            aperture_shape: ApertureShape::Circle,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
 
-    pub fn get_ray(&self, u: f64, v: f64) -> Ray {
+    /// Widens or narrows the view to `aspect_ratio`, keeping its centre and
+    /// vertical field of view.
+    pub fn with_aspect_ratio(mut self, aspect_ratio: f64) -> Self {
+        let centre = self.lower_left_corner + 0.5 * self.horizontal + 0.5 * self.vertical;
+        self.horizontal = self.u * self.vertical.norm() * aspect_ratio;
+        self.lower_left_corner = centre - 0.5 * self.horizontal - 0.5 * self.vertical;
+        self
+    }
+
+    /// Sets the bokeh shape; only visible with a non-zero aperture.
+    pub fn with_aperture_shape(mut self, shape: ApertureShape) -> Self {
+        self.aperture_shape = shape;
+        self
+    }
+
+    /// Keeps the shutter open from `open` to `close`; rays are spread
+    /// uniformly over that interval, blurring anything that moves.
+    pub fn with_shutter(mut self, open: f64, close: f64) -> Self {
+        self.shutter_open = open;
+        self.shutter_close = close;
+        self
+    }
+
+    pub fn get_ray(&self, s: f64, t: f64, rng: &mut Random) -> Ray {
+        let offset = if self.lens_radius > 0.0 {
+            let rd = self.lens_radius * self.aperture_shape.sample(rng);
+            self.u * rd[0] + self.v * rd[1]
+        } else {
+            Vector3::zeros()
+        };
+        let time = if self.shutter_close > self.shutter_open {
+            self.shutter_open + rng.sample_1d() * (self.shutter_close - self.shutter_open)
+        } else {
+            self.shutter_open
+        };
         Ray {
-            origin: self.origin,
+            origin: self.origin + offset,
             direction: Unit::new_normalize(
-                self.lower_left_corner + self.horizontal.mul(u) + self.vertical.mul(v)
-                    - self.origin,
+                self.lower_left_corner + self.horizontal.mul(s) + self.vertical.mul(t)
+                    - self.origin
+                    - offset,
             ),
+            time,
         }
     }
 }
@@ -0,0 +1,71 @@
+use nalgebra::vector;
+
+use super::{Background, Camera, Scene};
+use crate::{
+    hittable::{Bvh, HittableList, Object, Plane, Sphere},
+    material::{Dielectric, Lambertian, MaterialKind, Metal},
+};
+
+/// The built-in three-sphere scene, used when no scene file is given.
+pub fn demo_scene(aspect_ratio: f64) -> (Scene, Camera) {
+    let mut world = HittableList::new();
+
+    let material_ground = MaterialKind::Diffuse(Lambertian {
+        albedo: vector![0.8, 0.8, 0.0].into(),
+    });
+    let material_centre = MaterialKind::Diffuse(Lambertian {
+        albedo: vector![0.1, 0.2, 0.5].into(),
+    });
+    let material_left = MaterialKind::Dielectric(Dielectric::new(1.5));
+    let material_right = MaterialKind::Metallic(Metal {
+        albedo: vector![0.8, 0.6, 0.2].into(),
+        fuzz: 0.0,
+    });
+
+    let materials = vec![
+        material_ground,
+        material_centre,
+        material_left,
+        material_right,
+    ];
+
+    world.add(Object::Plane(Plane::new(
+        vector![0.0, -0.5, 0.0],
+        vector![0.0, 1.0, 0.0],
+        0,
+    )));
+    world.add(Object::Sphere(Sphere {
+        centre: vector![0.0, -0.0, -1.0],
+        radius: 0.5,
+        material_handle: 1,
+    }));
+    world.add(Object::Sphere(Sphere {
+        centre: vector![-1.0, 0.0, -1.0],
+        radius: 0.5,
+        material_handle: 2,
+    }));
+    world.add(Object::Sphere(Sphere {
+        centre: vector![1.0, 0.0, -1.0],
+        radius: 0.5,
+        material_handle: 3,
+    }));
+
+    let scene = Scene {
+        world: Object::Bvh(Bvh::new(world, 0.0, 0.0)),
+        materials,
+        background: Background::default(),
+        lights: Vec::new(),
+    };
+    let look_from = vector![-2.0, 2.0, 1.0];
+    let look_at = vector![0.0, 0.0, -1.0];
+    let cam = Camera::new(
+        look_from,
+        look_at,
+        vector![0.0, 1.0, 0.0],
+        20.0,
+        aspect_ratio,
+        0.0,
+        (look_from - look_at).norm(),
+    );
+    (scene, cam)
+}
@@ -1,10 +1,37 @@
+use std::time::Duration;
+
+use nalgebra::vector;
+use serde::Deserialize;
+
+use crate::{
+    ray::{luminance, ToneMap},
+    sampler::SamplerKind,
+    tile::RenderMode,
+};
+
 #[derive(Debug, Clone)]
 pub struct Image {
     pub aspect_ratio: f64,
     pub width: u64,
     pub height: u64,
+    /// Samples per pixel; with `adaptive` set, the most any pixel takes.
     pub samples: u64,
     pub max_depth: u64,
+    /// Bounces after which paths may be terminated by Russian roulette.
+    pub roulette_depth: u64,
+    pub tone_map: ToneMap,
+    /// Also record first-hit normals, depth and sample counts for HDR export.
+    pub extra_channels: bool,
+    pub render_mode: RenderMode,
+    pub sampler: SamplerKind,
+    /// Seed for all sampling; equal seeds give identical images.
+    pub seed: u64,
+    /// Worker threads to render with; zero uses one per CPU.
+    pub threads: usize,
+    pub adaptive: Option<Adaptive>,
+    /// Once this much time has passed, pixels stop sampling as soon as they
+    /// have their minimum number of samples.
+    pub time_limit: Option<Duration>,
 }
 
 impl Image {
@@ -16,6 +43,60 @@ impl Image {
             height,
             samples,
             max_depth,
+            roulette_depth: 5,
+            tone_map: ToneMap::default(),
+            extra_channels: false,
+            render_mode: RenderMode::default(),
+            sampler: SamplerKind::default(),
+            seed: 0,
+            threads: 0,
+            adaptive: None,
+            time_limit: None,
+        }
+    }
+
+    /// Samples every pixel takes regardless of its error or the time limit.
+    pub fn min_samples(&self) -> u64 {
+        self.adaptive
+            .map_or(1, |adaptive| adaptive.min_samples)
+            .clamp(1, self.samples.max(1))
+    }
+}
+
+/// Stops sampling pixels once their estimated error is small enough.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Adaptive {
+    /// Largest acceptable standard error of a pixel's mean luminance,
+    /// relative to that luminance. Pixels darker than 0.1 are held to the
+    /// error a pixel of 0.1 would be allowed, so they do not sample forever.
+    pub threshold: f64,
+    /// Samples taken before the error estimate is trusted.
+    #[serde(default = "default_min_samples")]
+    pub min_samples: u64,
+}
+
+fn default_min_samples() -> u64 {
+    16
+}
+
+impl Adaptive {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            min_samples: default_min_samples(),
+        }
+    }
+
+    /// Whether a pixel with radiance sums `sum` (red, green, blue, then
+    /// squared luminance) over `count` samples needs no more samples.
+    pub fn converged(&self, sum: &[f64], count: u32) -> bool {
+        if u64::from(count) < self.min_samples.max(2) {
+            return false;
         }
+        let n = f64::from(count);
+        let mean = luminance(&vector![sum[0], sum[1], sum[2]]) / n;
+        let variance = ((sum[3] / n - mean * mean) * n / (n - 1.0)).max(0.0);
+        (variance / n).sqrt() <= self.threshold * mean.max(0.1)
     }
 }
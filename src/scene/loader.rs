@@ -0,0 +1,1213 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+use nalgebra::{vector, Matrix4, Vector2, Vector3};
+use serde::Deserialize;
+
+use super::{Adaptive, ApertureShape, Background, Camera, EnvironmentMap, Image, Scene, Sky};
+use crate::{
+    hittable::{
+        obj::load_obj, Bvh, Cuboid, Disk, HittableList, MovingSphere, Object, Plane, Quad, Sphere,
+        Transformed,
+    },
+    material::{
+        Conductor, ConductorPreset, Dielectric, DiffuseLight, Lambertian, MaterialKind, Metal,
+        Principled,
+    },
+    ray::ToneMap,
+    texture::{Checker, CheckerMapping, ImageTexture, NoiseKind, NoiseTexture, TextureKind},
+    tile::RenderMode,
+    utility::degrees_to_radians,
+};
+
+/// Errors produced while reading a scene description.
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    /// Malformed TOML or a value of the wrong type; the message carries the
+    /// offending key and line.
+    Parse(toml::de::Error),
+    /// A well-formed file that does not describe a valid scene.
+    Invalid {
+        field: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "{}", e),
+            SceneError::Parse(e) => write!(f, "{}", e),
+            SceneError::Invalid { field, message } => write!(f, "{}: {}", field, message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+fn invalid(field: impl Into<String>, message: impl Into<String>) -> SceneError {
+    SceneError::Invalid {
+        field: field.into(),
+        message: message.into(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    image: ImageDesc,
+    camera: CameraDesc,
+    /// A `[r, g, b]` colour or a `BackgroundDesc` table; the sky gradient is
+    /// used when absent.
+    background: Option<toml::Value>,
+    // Materials and objects are kept as raw values and deserialized one at a
+    // time, so errors inside a tagged entry can name the entry they came from.
+    #[serde(default)]
+    materials: BTreeMap<String, toml::Value>,
+    #[serde(default)]
+    objects: Vec<toml::Value>,
+    /// Objects that are only placed in the scene by `instance` objects, which
+    /// all share one copy of each.
+    #[serde(default)]
+    shapes: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImageDesc {
+    width: u64,
+    height: Option<u64>,
+    aspect_ratio: Option<f64>,
+    #[serde(default = "default_samples")]
+    samples: u64,
+    #[serde(default = "default_max_depth")]
+    max_depth: u64,
+    #[serde(default = "default_roulette_depth")]
+    roulette_depth: u64,
+    #[serde(default)]
+    tone_map: ToneMap,
+    #[serde(default)]
+    extra_channels: bool,
+    /// e.g. `{ mode = "tiled", size = 32, order = "hilbert" }`.
+    #[serde(default)]
+    render: RenderMode,
+    /// One of `independent`, `stratified`, `halton` or `sobol`.
+    sampler: Option<String>,
+    #[serde(default)]
+    seed: u64,
+    #[serde(default)]
+    threads: usize,
+    /// e.g. `{ threshold = 0.01, min_samples = 16 }`.
+    adaptive: Option<Adaptive>,
+    /// Seconds after which pixels stop once they have their minimum samples.
+    time_limit: Option<f64>,
+}
+
+fn default_samples() -> u64 {
+    100
+}
+
+fn default_max_depth() -> u64 {
+    50
+}
+
+fn default_roulette_depth() -> u64 {
+    5
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    look_from: [f64; 3],
+    look_at: [f64; 3],
+    #[serde(default = "default_up")]
+    up: [f64; 3],
+    vfov: f64,
+    /// Lens diameter; zero gives a pinhole camera.
+    #[serde(default)]
+    aperture: f64,
+    /// Distance to the plane of focus; defaults to the `look_at` distance.
+    focus_distance: Option<f64>,
+    bokeh: Option<BokehDesc>,
+    /// `[open, close]` times; objects that move while the shutter is open blur.
+    #[serde(default)]
+    shutter: [f64; 2],
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+#[serde(expecting = "a bokeh table with a `type`")]
+enum BokehDesc {
+    Circle,
+    Polygon {
+        blades: u32,
+        #[serde(default)]
+        rotation: f64,
+    },
+    Custom {
+        points: Vec<[f64; 2]>,
+    },
+}
+
+fn default_up() -> [f64; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+#[serde(expecting = "a material table with a `type`")]
+enum MaterialDesc {
+    Lambertian {
+        albedo: toml::Value,
+    },
+    Metal {
+        albedo: toml::Value,
+        #[serde(default)]
+        fuzz: f64,
+    },
+    /// A rough metal given either by name (`metal = "gold"`) or by its
+    /// complex index of refraction, `eta` and `k`. `roughness` may be a
+    /// pair, for different roughness along and across the surface.
+    Conductor {
+        metal: Option<String>,
+        eta: Option<[f64; 3]>,
+        k: Option<[f64; 3]>,
+        #[serde(default)]
+        roughness: RoughnessDesc,
+    },
+    /// Glass and other clear media. `tint` is the colour white light turns
+    /// after travelling `tint_distance` inside.
+    Dielectric {
+        ri: f64,
+        #[serde(default)]
+        roughness: f64,
+        tint: Option<[f64; 3]>,
+        #[serde(default = "default_tint_distance")]
+        tint_distance: f64,
+    },
+    DiffuseLight {
+        emit: [f64; 3],
+    },
+    /// The Disney principled BSDF. Every parameter but `base_colour` and
+    /// `ior` lies between 0 and 1.
+    Principled {
+        base_colour: toml::Value,
+        #[serde(default)]
+        metallic: f64,
+        #[serde(default = "default_principled_roughness")]
+        roughness: f64,
+        #[serde(default = "default_principled_specular")]
+        specular: f64,
+        #[serde(default)]
+        specular_tint: f64,
+        #[serde(default)]
+        sheen: f64,
+        #[serde(default = "default_principled_sheen_tint")]
+        sheen_tint: f64,
+        #[serde(default)]
+        clearcoat: f64,
+        #[serde(default = "default_principled_clearcoat_gloss")]
+        clearcoat_gloss: f64,
+        #[serde(default)]
+        transmission: f64,
+        #[serde(default = "default_principled_ior")]
+        ior: f64,
+        #[serde(default)]
+        subsurface: f64,
+    },
+}
+
+fn default_principled_roughness() -> f64 {
+    0.5
+}
+
+fn default_principled_specular() -> f64 {
+    0.5
+}
+
+fn default_principled_sheen_tint() -> f64 {
+    0.5
+}
+
+fn default_principled_clearcoat_gloss() -> f64 {
+    1.0
+}
+
+fn default_principled_ior() -> f64 {
+    1.5
+}
+
+fn default_tint_distance() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+#[serde(expecting = "a roughness or an `[x, y]` pair of roughnesses")]
+enum RoughnessDesc {
+    Isotropic(f64),
+    Anisotropic([f64; 2]),
+}
+
+impl Default for RoughnessDesc {
+    fn default() -> Self {
+        RoughnessDesc::Isotropic(0.0)
+    }
+}
+
+impl RoughnessDesc {
+    fn pair(self) -> [f64; 2] {
+        match self {
+            RoughnessDesc::Isotropic(r) => [r, r],
+            RoughnessDesc::Anisotropic(pair) => pair,
+        }
+    }
+}
+
+/// A texture given as a table. Anywhere a texture is expected a plain
+/// `[r, g, b]` colour may be used instead.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+#[serde(expecting = "an `[r, g, b]` colour or a texture table with a `type`")]
+enum TextureDesc {
+    Solid {
+        colour: [f64; 3],
+    },
+    Checker {
+        even: toml::Value,
+        odd: toml::Value,
+        #[serde(default = "default_checker_scale")]
+        scale: f64,
+        #[serde(default = "default_checker_mapping")]
+        mapping: CheckerMapping,
+    },
+    Image {
+        file: String,
+    },
+    Noise {
+        #[serde(default = "default_noise_kind")]
+        kind: NoiseKind,
+        #[serde(default = "default_noise_colour")]
+        colour: [f64; 3],
+        #[serde(default = "default_noise_scale")]
+        scale: f64,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+fn default_checker_scale() -> f64 {
+    10.0
+}
+
+fn default_checker_mapping() -> CheckerMapping {
+    CheckerMapping::Uv
+}
+
+fn default_noise_kind() -> NoiseKind {
+    NoiseKind::Perlin
+}
+
+fn default_noise_colour() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_noise_scale() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+#[serde(expecting = "an `[r, g, b]` colour or a background table with a `type`")]
+enum BackgroundDesc {
+    Solid {
+        colour: [f64; 3],
+    },
+    Gradient {
+        #[serde(default = "default_gradient_bottom")]
+        bottom: [f64; 3],
+        #[serde(default = "default_gradient_top")]
+        top: [f64; 3],
+    },
+    /// An equirectangular `.hdr` or `.exr` image, turned `rotation` degrees
+    /// about the vertical axis and scaled by `intensity`.
+    Environment {
+        file: String,
+        #[serde(default)]
+        rotation: f64,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+    },
+    /// A daylight sky with the sun `elevation` degrees up and `azimuth`
+    /// degrees clockwise from -z.
+    Sky {
+        #[serde(default = "default_sun_elevation")]
+        elevation: f64,
+        #[serde(default)]
+        azimuth: f64,
+        #[serde(default = "default_turbidity")]
+        turbidity: f64,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+        /// Multiple of the real sun's size; larger suns give softer shadows.
+        #[serde(default = "default_sun_size")]
+        sun_size: f64,
+    },
+}
+
+fn default_gradient_bottom() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_gradient_top() -> [f64; 3] {
+    [0.5, 0.7, 1.0]
+}
+
+fn default_intensity() -> f64 {
+    1.0
+}
+
+fn default_sun_elevation() -> f64 {
+    45.0
+}
+
+fn default_turbidity() -> f64 {
+    3.0
+}
+
+fn default_sun_size() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+#[serde(expecting = "an object table with a `type`")]
+enum ObjectDesc {
+    Sphere {
+        centre: [f64; 3],
+        radius: f64,
+        material: String,
+    },
+    /// A sphere following `keyframes`, interpolated linearly in time.
+    MovingSphere {
+        keyframes: Vec<KeyframeDesc>,
+        radius: f64,
+        material: String,
+    },
+    /// A Wavefront OBJ file. Faces use the scene material named by `usemtl`,
+    /// or its entry in `material_map`, falling back to `material`.
+    Mesh {
+        file: String,
+        material: String,
+        #[serde(default)]
+        material_map: BTreeMap<String, String>,
+    },
+    /// An infinite plane through `point`, facing along `normal`.
+    Plane {
+        point: [f64; 3],
+        normal: [f64; 3],
+        material: String,
+    },
+    /// A parallelogram with a corner at `origin` and sides `u` and `v`.
+    Quad {
+        origin: [f64; 3],
+        u: [f64; 3],
+        v: [f64; 3],
+        material: String,
+    },
+    /// An axis-aligned rectangle at `offset` along `axis`, spanning `min` to
+    /// `max` in the other two coordinates (y, z for x; z, x for y; x, y for
+    /// z). It faces along the positive axis.
+    Rect {
+        axis: AxisDesc,
+        min: [f64; 2],
+        max: [f64; 2],
+        offset: f64,
+        material: String,
+    },
+    Disk {
+        centre: [f64; 3],
+        normal: [f64; 3],
+        radius: f64,
+        material: String,
+    },
+    /// An axis-aligned box between two opposite corners.
+    Box {
+        min: [f64; 3],
+        max: [f64; 3],
+        material: String,
+    },
+    /// A copy of one of the scene's `shapes`, scaled, then rotated by
+    /// `rotate` degrees about x, y and z in turn, then moved by `translate`.
+    Instance {
+        shape: String,
+        #[serde(default)]
+        scale: ScaleDesc,
+        #[serde(default)]
+        rotate: [f64; 3],
+        #[serde(default)]
+        translate: [f64; 3],
+    },
+}
+
+/// A single factor for all three axes, or one per axis.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+#[serde(expecting = "a scale factor or an `[x, y, z]` list of them")]
+enum ScaleDesc {
+    Uniform(f64),
+    PerAxis([f64; 3]),
+}
+
+impl Default for ScaleDesc {
+    fn default() -> Self {
+        ScaleDesc::Uniform(1.0)
+    }
+}
+
+impl ScaleDesc {
+    fn factors(self) -> Vector3<f64> {
+        match self {
+            ScaleDesc::Uniform(s) => vector![s, s, s],
+            ScaleDesc::PerAxis(s) => vec3(s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AxisDesc {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeDesc {
+    time: f64,
+    centre: [f64; 3],
+}
+
+fn vec3(v: [f64; 3]) -> Vector3<f64> {
+    vector![v[0], v[1], v[2]]
+}
+
+/// Reads a TOML scene description from `path`. Files it references, such as
+/// meshes, are resolved relative to the scene file.
+pub fn load_scene(path: impl AsRef<Path>) -> Result<(Scene, Camera, Image), SceneError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(SceneError::Io)?;
+    parse_scene(&source, path.parent().unwrap_or_else(|| Path::new("")))
+}
+
+pub fn parse_scene(source: &str, base_dir: &Path) -> Result<(Scene, Camera, Image), SceneError> {
+    let desc: SceneDesc = toml::from_str(source).map_err(SceneError::Parse)?;
+
+    let image = build_image(&desc.image)?;
+    let camera = build_camera(&desc.camera, image.aspect_ratio)?;
+
+    let mut handles = BTreeMap::new();
+    let mut materials = Vec::with_capacity(desc.materials.len());
+    for (name, value) in desc.materials {
+        let field = format!("materials.{}", name);
+        let material: MaterialDesc = deserialize_entry(&field, value)?;
+        materials.push(build_material(&field, &material, base_dir)?);
+        handles.insert(name, materials.len() - 1);
+    }
+
+    // Shapes cannot instance one another.
+    let mut shapes = BTreeMap::new();
+    for (name, value) in desc.shapes {
+        let field = format!("shapes.{}", name);
+        let shape: ObjectDesc = deserialize_entry(&field, value)?;
+        let shape = build_object(&field, &shape, &handles, &BTreeMap::new(), base_dir)?;
+        shapes.insert(name, Arc::new(shape));
+    }
+
+    let mut world = HittableList::new();
+    let mut lights = Vec::new();
+    for (i, value) in desc.objects.into_iter().enumerate() {
+        let field = format!("objects[{}]", i);
+        let object: ObjectDesc = deserialize_entry(&field, value)?;
+        let object = build_object(&field, &object, &handles, &shapes, base_dir)?;
+        if let Some(handle) = light_material(&object) {
+            if let MaterialKind::Emissive(_) = materials[handle] {
+                lights.push(object.clone());
+            }
+        }
+        world.add(object);
+    }
+
+    let scene = Scene {
+        world: Object::Bvh(Bvh::new(world, camera.shutter_open, camera.shutter_close)),
+        materials,
+        background: match &desc.background {
+            Some(value) => build_background("background", value, base_dir)?,
+            None => Background::default(),
+        },
+        lights,
+    };
+    Ok((scene, camera, image))
+}
+
+/// The material of an object that can be sampled directly as a light: a
+/// static sphere, a flat shape or an instance of one. Other emitters are
+/// still found by BSDF sampling.
+fn light_material(object: &Object) -> Option<usize> {
+    match object {
+        Object::Sphere(sphere) => Some(sphere.material_handle),
+        Object::Quad(quad) => Some(quad.material_handle),
+        Object::Disk(disk) => Some(disk.material_handle),
+        Object::Box(cuboid) => Some(cuboid.material_handle),
+        Object::Transformed(instance) => light_material(instance.object()),
+        _ => None,
+    }
+}
+
+/// Deserializes one entry of the scene. Tagged tables are buffered before
+/// their fields are read, so serde's errors do not say which key was wrong;
+/// when a table fails, the key is found again by leaving each one out in turn
+/// and seeing whether the error goes away or changes.
+fn deserialize_entry<T: serde::de::DeserializeOwned>(
+    field: &str,
+    value: toml::Value,
+) -> Result<T, SceneError> {
+    let error = match value.clone().try_into::<T>() {
+        Ok(entry) => return Ok(entry),
+        Err(e) => e.to_string(),
+    };
+    // A missing field already names itself, and leaving out another key
+    // would only report that one missing instead.
+    let table = match &value {
+        toml::Value::Table(table) if !error.starts_with("missing field") => table,
+        _ => return Err(invalid(field, error)),
+    };
+    for key in table.keys().filter(|key| *key != "type") {
+        let mut rest = table.clone();
+        rest.remove(key);
+        match toml::Value::Table(rest).try_into::<T>() {
+            Err(e) if e.to_string() == error => {}
+            _ => return Err(invalid(format!("{}.{}", field, key), error)),
+        }
+    }
+    Err(invalid(field, error))
+}
+
+fn build_image(desc: &ImageDesc) -> Result<Image, SceneError> {
+    if desc.width == 0 {
+        return Err(invalid("image.width", "must be greater than zero"));
+    }
+    let aspect_ratio = match (desc.height, desc.aspect_ratio) {
+        (Some(_), Some(_)) => {
+            return Err(invalid(
+                "image",
+                "set either `height` or `aspect_ratio`, not both",
+            ))
+        }
+        (Some(0), None) => return Err(invalid("image.height", "must be greater than zero")),
+        (Some(height), None) => desc.width as f64 / height as f64,
+        (None, Some(ratio)) if ratio > 0.0 => ratio,
+        (None, Some(_)) => return Err(invalid("image.aspect_ratio", "must be greater than zero")),
+        (None, None) => 16.0 / 9.0,
+    };
+    if desc.samples == 0 {
+        return Err(invalid("image.samples", "must be greater than zero"));
+    }
+
+    let mut image = Image::new(aspect_ratio, desc.width, desc.samples, desc.max_depth);
+    if image.height == 0 {
+        return Err(invalid(
+            "image.width",
+            format!(
+                "{} is too small for an aspect ratio of {:.3}: the height rounds to 0",
+                desc.width, aspect_ratio
+            ),
+        ));
+    }
+    image.roulette_depth = desc.roulette_depth;
+    image.tone_map = desc.tone_map;
+    image.extra_channels = desc.extra_channels;
+    if let RenderMode::Tiled { size: 0, .. } = desc.render {
+        return Err(invalid("image.render.size", "must be greater than zero"));
+    }
+    image.render_mode = desc.render;
+    if let Some(sampler) = &desc.sampler {
+        image.sampler = sampler
+            .parse()
+            .map_err(|e: String| invalid("image.sampler", e))?;
+    }
+    image.seed = desc.seed;
+    image.threads = desc.threads;
+    if let Some(adaptive) = desc.adaptive {
+        if adaptive.threshold.is_nan() || adaptive.threshold <= 0.0 {
+            return Err(invalid(
+                "image.adaptive.threshold",
+                "must be greater than zero",
+            ));
+        }
+        image.adaptive = Some(adaptive);
+    }
+    if let Some(seconds) = desc.time_limit {
+        if !(seconds > 0.0 && seconds.is_finite()) {
+            return Err(invalid(
+                "image.time_limit",
+                "must be a positive number of seconds",
+            ));
+        }
+        image.time_limit = Some(Duration::from_secs_f64(seconds));
+    }
+    if let Some(height) = desc.height {
+        image.height = height;
+    }
+    Ok(image)
+}
+
+fn build_camera(desc: &CameraDesc, aspect_ratio: f64) -> Result<Camera, SceneError> {
+    if !(desc.vfov > 0.0 && desc.vfov < 180.0) {
+        return Err(invalid(
+            "camera.vfov",
+            format!(
+                "expected an angle between 0 and 180 degrees, got {}",
+                desc.vfov
+            ),
+        ));
+    }
+    if desc.look_from == desc.look_at {
+        return Err(invalid("camera", "`look_from` and `look_at` must differ"));
+    }
+    if desc.aperture < 0.0 {
+        return Err(invalid("camera.aperture", "must not be negative"));
+    }
+    let look_from = vec3(desc.look_from);
+    let look_at = vec3(desc.look_at);
+    let up = vec3(desc.up);
+    let view = look_from - look_at;
+    if up.cross(&view).norm() <= 1e-9 * up.norm() * view.norm() {
+        return Err(invalid(
+            "camera.up",
+            "must be non-zero and not parallel to the direction from `look_at` to `look_from`",
+        ));
+    }
+    let focus_dist = desc
+        .focus_distance
+        .unwrap_or_else(|| (look_from - look_at).norm());
+    if focus_dist <= 0.0 {
+        return Err(invalid(
+            "camera.focus_distance",
+            "must be greater than zero",
+        ));
+    }
+
+    let [shutter_open, shutter_close] = desc.shutter;
+    if shutter_close < shutter_open {
+        return Err(invalid(
+            "camera.shutter",
+            "the shutter must close after it opens",
+        ));
+    }
+
+    let aperture_shape = match &desc.bokeh {
+        None | Some(BokehDesc::Circle) => ApertureShape::Circle,
+        Some(BokehDesc::Polygon { blades, rotation }) => {
+            if *blades < 3 {
+                return Err(invalid(
+                    "camera.bokeh.blades",
+                    format!("a polygon needs at least 3 blades, got {}", blades),
+                ));
+            }
+            ApertureShape::Polygon {
+                blades: *blades,
+                rotation: *rotation,
+            }
+        }
+        Some(BokehDesc::Custom { points }) => {
+            if points.len() < 3 {
+                return Err(invalid(
+                    "camera.bokeh.points",
+                    "a custom aperture needs at least 3 points",
+                ));
+            }
+            if points.iter().flatten().any(|c| c.abs() > 1.0) {
+                return Err(invalid(
+                    "camera.bokeh.points",
+                    "points must lie within -1..1 on both axes",
+                ));
+            }
+            ApertureShape::Custom {
+                points: points.iter().map(|p| Vector2::new(p[0], p[1])).collect(),
+            }
+        }
+    };
+
+    Ok(Camera::new(
+        look_from,
+        look_at,
+        up,
+        desc.vfov,
+        aspect_ratio,
+        desc.aperture,
+        focus_dist,
+    )
+    .with_aperture_shape(aperture_shape)
+    .with_shutter(shutter_open, shutter_close))
+}
+
+fn build_background(
+    field: &str,
+    value: &toml::Value,
+    base_dir: &Path,
+) -> Result<Background, SceneError> {
+    if value.is_array() {
+        let colour: [f64; 3] = deserialize_entry(field, value.clone())?;
+        return Ok(Background::Solid(vec3(colour)));
+    }
+    let desc: BackgroundDesc = deserialize_entry(field, value.clone())?;
+    let field = |key: &str| format!("{}.{}", field, key);
+    Ok(match desc {
+        BackgroundDesc::Solid { colour } => Background::Solid(vec3(colour)),
+        BackgroundDesc::Gradient { bottom, top } => Background::Gradient {
+            bottom: vec3(bottom),
+            top: vec3(top),
+        },
+        BackgroundDesc::Environment {
+            file,
+            rotation,
+            intensity,
+        } => {
+            if intensity < 0.0 {
+                return Err(invalid(field("intensity"), "must not be negative"));
+            }
+            Background::Environment(
+                EnvironmentMap::open(base_dir.join(&file), rotation, intensity)
+                    .map_err(|e| invalid(field("file"), format!("'{}': {}", file, e)))?,
+            )
+        }
+        BackgroundDesc::Sky {
+            elevation,
+            azimuth,
+            turbidity,
+            intensity,
+            sun_size,
+        } => {
+            if !(0.0..=90.0).contains(&elevation) {
+                return Err(invalid(
+                    field("elevation"),
+                    format!(
+                        "expected an angle between 0 and 90 degrees, got {}",
+                        elevation
+                    ),
+                ));
+            }
+            if !(1.7..=10.0).contains(&turbidity) {
+                return Err(invalid(
+                    field("turbidity"),
+                    format!("expected a value between 1.7 and 10, got {}", turbidity),
+                ));
+            }
+            if intensity < 0.0 {
+                return Err(invalid(field("intensity"), "must not be negative"));
+            }
+            if sun_size <= 0.0 {
+                return Err(invalid(field("sun_size"), "must be greater than zero"));
+            }
+            Background::Sky(
+                Sky::new(elevation, azimuth, turbidity)
+                    .with_intensity(intensity)
+                    .with_sun_size(sun_size),
+            )
+        }
+    })
+}
+
+fn build_texture(
+    field: &str,
+    value: &toml::Value,
+    base_dir: &Path,
+) -> Result<TextureKind, SceneError> {
+    if value.is_array() {
+        let colour: [f64; 3] = deserialize_entry(field, value.clone())?;
+        return Ok(vec3(colour).into());
+    }
+    let desc: TextureDesc = deserialize_entry(field, value.clone())?;
+    let field = |key: &str| format!("{}.{}", field, key);
+    Ok(match desc {
+        TextureDesc::Solid { colour } => vec3(colour).into(),
+        TextureDesc::Checker {
+            even,
+            odd,
+            scale,
+            mapping,
+        } => {
+            if scale <= 0.0 {
+                return Err(invalid(field("scale"), "must be greater than zero"));
+            }
+            TextureKind::Checker(Checker {
+                even: Box::new(build_texture(&field("even"), &even, base_dir)?),
+                odd: Box::new(build_texture(&field("odd"), &odd, base_dir)?),
+                scale,
+                mapping,
+            })
+        }
+        TextureDesc::Image { file } => TextureKind::Image(
+            ImageTexture::open(base_dir.join(&file))
+                .map_err(|e| invalid(field("file"), format!("'{}': {}", file, e)))?,
+        ),
+        TextureDesc::Noise {
+            kind,
+            colour,
+            scale,
+            seed,
+        } => TextureKind::Noise(NoiseTexture::new(kind, vec3(colour), scale, seed)),
+    })
+}
+
+fn build_material(
+    field: &str,
+    desc: &MaterialDesc,
+    base_dir: &Path,
+) -> Result<MaterialKind, SceneError> {
+    let texture = |key: &str, value| build_texture(&format!("{}.{}", field, key), value, base_dir);
+    let field = |key: &str| format!("{}.{}", field, key);
+    Ok(match *desc {
+        MaterialDesc::Lambertian { ref albedo } => MaterialKind::Diffuse(Lambertian {
+            albedo: texture("albedo", albedo)?,
+        }),
+        MaterialDesc::Metal { ref albedo, fuzz } => {
+            if !(0.0..=1.0).contains(&fuzz) {
+                return Err(invalid(
+                    field("fuzz"),
+                    format!("expected a value between 0 and 1, got {}", fuzz),
+                ));
+            }
+            MaterialKind::Metallic(Metal {
+                albedo: texture("albedo", albedo)?,
+                fuzz,
+            })
+        }
+        MaterialDesc::Conductor {
+            ref metal,
+            eta,
+            k,
+            roughness,
+        } => {
+            let roughness = roughness.pair();
+            if roughness.iter().any(|r| !(0.0..=1.0).contains(r)) {
+                return Err(invalid(
+                    field("roughness"),
+                    format!("expected values between 0 and 1, got {:?}", roughness),
+                ));
+            }
+            let (eta, k) = match (metal, eta, k) {
+                (Some(metal), None, None) => metal
+                    .parse::<ConductorPreset>()
+                    .map_err(|e| invalid(field("metal"), e))?
+                    .ior(),
+                (None, Some(eta), Some(k)) => {
+                    if eta.iter().chain(&k).any(|c| *c < 0.0) {
+                        return Err(invalid(field("eta"), "`eta` and `k` must not be negative"));
+                    }
+                    (vec3(eta), vec3(k))
+                }
+                _ => {
+                    return Err(invalid(
+                        field("metal"),
+                        "set either `metal` or both `eta` and `k`",
+                    ))
+                }
+            };
+            MaterialKind::Conductor(Conductor::new(eta, k, roughness[0], roughness[1]))
+        }
+        MaterialDesc::Dielectric {
+            ri,
+            roughness,
+            tint,
+            tint_distance,
+        } => {
+            if ri <= 0.0 {
+                return Err(invalid(field("ri"), "must be greater than zero"));
+            }
+            if !(0.0..=1.0).contains(&roughness) {
+                return Err(invalid(
+                    field("roughness"),
+                    format!("expected a value between 0 and 1, got {}", roughness),
+                ));
+            }
+            let mut dielectric = Dielectric::new(ri).with_roughness(roughness);
+            if let Some(tint) = tint {
+                if tint.iter().any(|c| !(*c > 0.0 && *c <= 1.0)) {
+                    return Err(invalid(
+                        field("tint"),
+                        "channels must be greater than 0 and at most 1",
+                    ));
+                }
+                if tint_distance <= 0.0 {
+                    return Err(invalid(field("tint_distance"), "must be greater than zero"));
+                }
+                dielectric = dielectric.with_tint(vec3(tint), tint_distance);
+            }
+            MaterialKind::Dielectric(dielectric)
+        }
+        MaterialDesc::DiffuseLight { emit } => {
+            if emit.iter().any(|c| *c < 0.0) {
+                return Err(invalid(field("emit"), "must not be negative"));
+            }
+            MaterialKind::Emissive(DiffuseLight { emit: vec3(emit) })
+        }
+        MaterialDesc::Principled {
+            ref base_colour,
+            metallic,
+            roughness,
+            specular,
+            specular_tint,
+            sheen,
+            sheen_tint,
+            clearcoat,
+            clearcoat_gloss,
+            transmission,
+            ior,
+            subsurface,
+        } => {
+            let unit_parameters = [
+                ("metallic", metallic),
+                ("roughness", roughness),
+                ("specular", specular),
+                ("specular_tint", specular_tint),
+                ("sheen", sheen),
+                ("sheen_tint", sheen_tint),
+                ("clearcoat", clearcoat),
+                ("clearcoat_gloss", clearcoat_gloss),
+                ("transmission", transmission),
+                ("subsurface", subsurface),
+            ];
+            for (name, value) in unit_parameters {
+                if !(0.0..=1.0).contains(&value) {
+                    return Err(invalid(
+                        field(name),
+                        format!("expected a value between 0 and 1, got {}", value),
+                    ));
+                }
+            }
+            if ior <= 0.0 {
+                return Err(invalid(field("ior"), "must be greater than zero"));
+            }
+            MaterialKind::Principled(Principled {
+                metallic,
+                roughness,
+                specular,
+                specular_tint,
+                sheen,
+                sheen_tint,
+                clearcoat,
+                clearcoat_gloss,
+                transmission,
+                ior,
+                subsurface,
+                ..Principled::new(texture("base_colour", base_colour)?)
+            })
+        }
+    })
+}
+
+fn material_handle(
+    field: &str,
+    name: &str,
+    handles: &BTreeMap<String, usize>,
+) -> Result<usize, SceneError> {
+    handles.get(name).copied().ok_or_else(|| {
+        let known = handles.keys().cloned().collect::<Vec<_>>().join(", ");
+        invalid(
+            field,
+            format!("unknown material '{}' (defined materials: {})", name, known),
+        )
+    })
+}
+
+fn build_object(
+    field: &str,
+    desc: &ObjectDesc,
+    handles: &BTreeMap<String, usize>,
+    shapes: &BTreeMap<String, Arc<Object>>,
+    base_dir: &Path,
+) -> Result<Object, SceneError> {
+    match desc {
+        ObjectDesc::Sphere {
+            centre,
+            radius,
+            material,
+        } => {
+            // Negative radii are allowed; they flip the normals to model hollow shells.
+            if *radius == 0.0 {
+                return Err(invalid(format!("{}.radius", field), "must be non-zero"));
+            }
+            Ok(Object::Sphere(Sphere {
+                centre: vec3(*centre),
+                radius: *radius,
+                material_handle: material_handle(
+                    &format!("{}.material", field),
+                    material,
+                    handles,
+                )?,
+            }))
+        }
+        ObjectDesc::MovingSphere {
+            keyframes,
+            radius,
+            material,
+        } => {
+            if keyframes.is_empty() {
+                return Err(invalid(
+                    format!("{}.keyframes", field),
+                    "needs at least one keyframe",
+                ));
+            }
+            if *radius == 0.0 {
+                return Err(invalid(format!("{}.radius", field), "must be non-zero"));
+            }
+            Ok(Object::MovingSphere(MovingSphere::new(
+                keyframes.iter().map(|k| (k.time, vec3(k.centre))).collect(),
+                *radius,
+                material_handle(&format!("{}.material", field), material, handles)?,
+            )))
+        }
+        ObjectDesc::Mesh {
+            file,
+            material,
+            material_map,
+        } => {
+            let default_material =
+                material_handle(&format!("{}.material", field), material, handles)?;
+            let mut obj_materials: HashMap<String, usize> = handles.clone().into_iter().collect();
+            for (obj_name, scene_name) in material_map {
+                let handle = material_handle(
+                    &format!("{}.material_map.{}", field, obj_name),
+                    scene_name,
+                    handles,
+                )?;
+                obj_materials.insert(obj_name.clone(), handle);
+            }
+            let mesh = load_obj(base_dir.join(file), &obj_materials, default_material)
+                .map_err(|e| invalid(format!("{}.file", field), format!("'{}': {}", file, e)))?;
+            Ok(Object::Mesh(mesh))
+        }
+        ObjectDesc::Plane {
+            point,
+            normal,
+            material,
+        } => {
+            if vec3(*normal) == Vector3::zeros() {
+                return Err(invalid(format!("{}.normal", field), "must be non-zero"));
+            }
+            Ok(Object::Plane(Plane::new(
+                vec3(*point),
+                vec3(*normal),
+                material_handle(&format!("{}.material", field), material, handles)?,
+            )))
+        }
+        ObjectDesc::Quad {
+            origin,
+            u,
+            v,
+            material,
+        } => {
+            if vec3(*u).cross(&vec3(*v)) == Vector3::zeros() {
+                return Err(invalid(
+                    format!("{}.v", field),
+                    "must not be parallel to u or zero length",
+                ));
+            }
+            Ok(Object::Quad(Quad::new(
+                vec3(*origin),
+                vec3(*u),
+                vec3(*v),
+                material_handle(&format!("{}.material", field), material, handles)?,
+            )))
+        }
+        ObjectDesc::Rect {
+            axis,
+            min,
+            max,
+            offset,
+            material,
+        } => {
+            if min[0] >= max[0] || min[1] >= max[1] {
+                return Err(invalid(
+                    format!("{}.max", field),
+                    "must be greater than min in both coordinates",
+                ));
+            }
+            Ok(Object::Quad(Quad::axis_aligned(
+                *axis as usize,
+                vector![min[0], min[1]],
+                vector![max[0], max[1]],
+                *offset,
+                material_handle(&format!("{}.material", field), material, handles)?,
+            )))
+        }
+        ObjectDesc::Disk {
+            centre,
+            normal,
+            radius,
+            material,
+        } => {
+            if vec3(*normal) == Vector3::zeros() {
+                return Err(invalid(format!("{}.normal", field), "must be non-zero"));
+            }
+            if *radius <= 0.0 {
+                return Err(invalid(format!("{}.radius", field), "must be positive"));
+            }
+            Ok(Object::Disk(Disk::new(
+                vec3(*centre),
+                vec3(*normal),
+                *radius,
+                material_handle(&format!("{}.material", field), material, handles)?,
+            )))
+        }
+        ObjectDesc::Box { min, max, material } => {
+            if (0..3).any(|i| min[i] >= max[i]) {
+                return Err(invalid(
+                    format!("{}.max", field),
+                    "must be greater than min on every axis",
+                ));
+            }
+            Ok(Object::Box(Cuboid::new(
+                vec3(*min),
+                vec3(*max),
+                material_handle(&format!("{}.material", field), material, handles)?,
+            )))
+        }
+        ObjectDesc::Instance {
+            shape,
+            scale,
+            rotate,
+            translate,
+        } => {
+            let object = shapes.get(shape).cloned().ok_or_else(|| {
+                let known = shapes.keys().cloned().collect::<Vec<_>>().join(", ");
+                invalid(
+                    format!("{}.shape", field),
+                    format!("unknown shape '{}' (defined shapes: {})", shape, known),
+                )
+            })?;
+            let factors = scale.factors();
+            if factors.iter().any(|s| *s == 0.0) {
+                return Err(invalid(format!("{}.scale", field), "must be non-zero"));
+            }
+            // Scaled first, then turned about x, y and z, then moved.
+            let turn = |axis, degrees| Matrix4::from_axis_angle(&axis, degrees_to_radians(degrees));
+            let transform = Matrix4::new_translation(&vec3(*translate))
+                * turn(Vector3::z_axis(), rotate[2])
+                * turn(Vector3::y_axis(), rotate[1])
+                * turn(Vector3::x_axis(), rotate[0])
+                * Matrix4::new_nonuniform_scaling(&factors);
+            let instance = Transformed::new(object, transform).map_err(|e| invalid(field, e))?;
+            Ok(Object::Transformed(instance))
+        }
+    }
+}
@@ -1,46 +1,196 @@
+mod aperture;
+mod background;
 mod camera;
+mod demo;
 mod image_data;
+mod loader;
+mod sky;
 
+pub use aperture::ApertureShape;
+pub use background::{Background, EnvironmentMap};
 pub use camera::Camera;
-pub use image_data::Image;
-use nalgebra::{vector, Vector3};
+pub use demo::demo_scene;
+pub use image_data::{Adaptive, Image};
+pub use loader::load_scene;
+use nalgebra::{vector, Unit, Vector3};
+pub use sky::Sky;
 
 use crate::{
-    hittable::{Hittable, Object},
+    hittable::{HitRecord, Hittable, Object},
     material::{Material, MaterialKind},
     ray::Ray,
-    utility::NamedField,
+    utility::Random,
 };
 
 #[derive(Debug, Clone)]
 pub struct Scene {
     pub world: Object,
     pub materials: Vec<MaterialKind>,
+    /// Light arriving along rays that escape the scene.
+    pub background: Background,
+    /// Emissive objects sampled directly at every diffuse bounce. They are
+    /// also part of `world`, which is what shadow rays are traced against.
+    pub lights: Vec<Object>,
 }
 
 impl Scene {
-    pub fn ray_colour(&self, ray: &Ray, depth: u64) -> Vector3<f64> {
-        if depth == 0 {
+    /// Radiance along `ray`, following at most `max_depth` bounces. Past
+    /// `roulette_depth` bounces paths are ended at random with a probability
+    /// that grows as their throughput falls, and survivors are scaled up to
+    /// compensate.
+    ///
+    /// Light reaching a diffuse surface is estimated twice, once by sampling
+    /// `lights` (and the background, if it is an environment map or has a sun) and once
+    /// by following the BSDF sample, and the two are combined with multiple
+    /// importance sampling.
+    pub fn ray_colour(
+        &self,
+        ray: &Ray,
+        max_depth: u64,
+        roulette_depth: u64,
+        rng: &mut Random,
+    ) -> Vector3<f64> {
+        let mut colour = vector![0.0, 0.0, 0.0];
+        let mut throughput = vector![1.0, 1.0, 1.0];
+        let mut ray = ray.clone();
+        // Density the previous bounce picked `ray` with, or `None` when that
+        // bounce took no light sample.
+        let mut bsdf_pdf: Option<f64> = None;
+
+        for depth in 0..max_depth {
+            rng.start_bounce(depth);
+            let hit = match self.world.hit(&ray, 0.001, f64::INFINITY) {
+                Some(hit) => hit,
+                None => {
+                    let mut background = self.background.colour(&ray.direction());
+                    if let Some(bsdf_pdf) = bsdf_pdf {
+                        let light_pdf = self.environment_pdf(&ray.direction());
+                        background *= power_heuristic(bsdf_pdf, light_pdf);
+                    }
+                    colour += throughput.component_mul(&background);
+                    break;
+                }
+            };
+            let material = &self.materials[hit.material_handle];
+            let mut emitted = material.emitted(&hit);
+            if let Some(bsdf_pdf) = bsdf_pdf {
+                let light_pdf = self.light_pdf(&ray.origin(), &ray.direction());
+                emitted *= power_heuristic(bsdf_pdf, light_pdf);
+            }
+            colour += throughput.component_mul(&emitted);
+
+            let wo = -ray.direction();
+            let sample = match material.sample(&hit, &wo, rng) {
+                Some(sample) => sample,
+                None => break,
+            };
+            // The light sample stands in for one more bounce, so take it only
+            // when the BSDF path could still pick up emission.
+            bsdf_pdf = sample.pdf.filter(|_| depth + 1 < max_depth);
+            if bsdf_pdf.is_some() {
+                colour += throughput.component_mul(&self.sample_lights(&ray, &hit, material, rng));
+            }
+            throughput = throughput.component_mul(&sample.attenuation);
+
+            if depth + 1 >= roulette_depth {
+                let survival = throughput.max().min(0.95);
+                if survival <= 0.0 || rng.sample_1d() >= survival {
+                    break;
+                }
+                throughput /= survival;
+            }
+            ray = Ray {
+                origin: hit.point,
+                direction: sample.wi,
+                time: ray.time(),
+            };
+        }
+        colour
+    }
+
+    /// Light arriving at `hit` along a direction chosen by sampling a random
+    /// light or the background, weighted against the chance the BSDF would
+    /// have picked it.
+    fn sample_lights(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        material: &MaterialKind,
+        rng: &mut Random,
+    ) -> Vector3<f64> {
+        let light_count = self.light_count();
+        if light_count == 0 {
             return vector![0.0, 0.0, 0.0];
         }
-        match self.world.hit(ray, 0.001, f64::INFINITY) {
-            Some(hit) => {
-                let scatter = self.materials[hit.material_handle].scatter(ray, &hit);
-                if let Some(r) = scatter.ray {
-                    self.ray_colour(&r, depth - 1)
-                        .component_mul(&scatter.attenuation)
-                } else {
-                    vector![0.0, 0.0, 0.0]
-                }
+        let index = rng.random_index(light_count);
+        let (direction, light_pdf) = match self.lights.get(index) {
+            Some(light) => {
+                let direction = light.random(&hit.point, rng);
+                (direction, self.light_pdf(&hit.point, &direction))
             }
             None => {
-                let t = 0.5 * (ray.direction().y() + 1.0);
-                (1.0 - t) * vector![1.0, 1.0, 1.0] + t * vector![0.5, 0.7, 1.0]
+                let direction = self.background.sample(rng);
+                (direction, self.environment_pdf(&direction))
             }
+        };
+        let wo = -ray_in.direction();
+        let bsdf = material.eval(hit, &wo, &direction);
+        if light_pdf <= 0.0 || bsdf == Vector3::zeros() {
+            return vector![0.0, 0.0, 0.0];
+        }
+
+        let shadow_ray = Ray {
+            origin: hit.point,
+            direction,
+            time: ray_in.time(),
+        };
+        // Each strategy only counts the light it aimed for: an object light
+        // that finds the background, or a background sample that is blocked,
+        // contributes nothing.
+        let emitted = match (
+            self.world.hit(&shadow_ray, 0.001, f64::INFINITY),
+            index < self.lights.len(),
+        ) {
+            (Some(light_hit), true) => {
+                self.materials[light_hit.material_handle].emitted(&light_hit)
+            }
+            (None, false) => self.background.colour(&direction),
+            _ => return vector![0.0, 0.0, 0.0],
+        };
+        let bsdf_pdf = material.pdf(hit, &wo, &direction);
+        emitted.component_mul(&bsdf) * power_heuristic(light_pdf, bsdf_pdf) / light_pdf
+    }
+
+    /// Number of choices `sample_lights` picks from: every light, plus the
+    /// background when it can be sampled.
+    fn light_count(&self) -> usize {
+        self.lights.len() + usize::from(self.background.is_sampled())
+    }
+
+    /// Density of `sample_lights` choosing `direction` from `origin` by
+    /// sampling an object light.
+    fn light_pdf(&self, origin: &Vector3<f64>, direction: &Unit<Vector3<f64>>) -> f64 {
+        if self.lights.is_empty() {
+            return 0.0;
         }
+        let total: f64 = self
+            .lights
+            .iter()
+            .map(|light| light.pdf_value(origin, direction))
+            .sum();
+        total / self.light_count() as f64
     }
+
+    /// Density of `sample_lights` choosing `direction` by sampling the background.
+    fn environment_pdf(&self, direction: &Unit<Vector3<f64>>) -> f64 {
+        if !self.background.is_sampled() {
+            return 0.0;
+        }
+        self.background.pdf(direction) / self.light_count() as f64
+    }
+
     #[allow(dead_code)]
-    pub fn trace_ray(&self, ray: &Ray, depth: u64) -> Vector3<f64> {
+    pub fn trace_ray(&self, ray: &Ray, depth: u64, rng: &mut Random) -> Vector3<f64> {
         if depth == 0 {
             println!("Ray hit max depth.");
             return vector![0.0, 0.0, 0.0];
@@ -54,19 +204,37 @@ impl Scene {
         match self.world.hit(ray, 0.001, f64::INFINITY) {
             Some(hit) => {
                 println!("Ray {} hit {:?}.", depth, hit);
-                let scatter = self.materials[hit.material_handle].scatter(ray, &hit);
-                if let Some(r) = scatter.ray {
-                    self.trace_ray(&r, depth - 1)
-                        .component_mul(&scatter.attenuation)
+                let material = &self.materials[hit.material_handle];
+                let emitted = material.emitted(&hit);
+                rng.start_bounce(depth);
+                if let Some(sample) = material.sample(&hit, &-ray.direction(), rng) {
+                    let scattered = Ray {
+                        origin: hit.point,
+                        direction: sample.wi,
+                        time: ray.time(),
+                    };
+                    emitted
+                        + self
+                            .trace_ray(&scattered, depth - 1, rng)
+                            .component_mul(&sample.attenuation)
                 } else {
-                    vector![0.0, 0.0, 0.0]
+                    emitted
                 }
             }
             None => {
                 println!("No hit.");
-                let t = 0.5 * (ray.direction().y() + 1.0);
-                (1.0 - t) * vector![1.0, 1.0, 1.0] + t * vector![0.5, 0.7, 1.0]
+                self.background.colour(&ray.direction())
             }
         }
     }
 }
+
+/// Veach's power heuristic (beta = 2) weight for a sample drawn with density
+/// `pdf` when another strategy would have drawn it with `other_pdf`.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    if other_pdf <= 0.0 {
+        return 1.0;
+    }
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    a / (a + b)
+}
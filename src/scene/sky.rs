@@ -0,0 +1,179 @@
+use std::f64::consts::PI;
+
+use nalgebra::{vector, Unit, Vector3};
+
+use crate::utility::{degrees_to_radians, sample_uniform_cone, uniform_cone_pdf, Onb, Random};
+
+/// Angular radius of the sun as seen from the ground.
+const SUN_RADIUS: f64 = 0.2667 * PI / 180.0;
+/// Luminance of the sun above the atmosphere, in kcd/m².
+const SUN_LUMINANCE: f64 = 2.0e6;
+/// Converts kcd/m² into scene radiance, so that a sunlit white surface comes
+/// out a little under one.
+const SKY_SCALE: f64 = 0.03;
+
+/// The Preetham daylight model: a clear sky lit by a sun at the given
+/// position, with `turbidity` setting how hazy the air is (2 is very clear,
+/// 10 is hazy). Includes the sun disk itself, dimmed and reddened by the air
+/// it shines through.
+#[derive(Debug, Clone)]
+pub struct Sky {
+    sun: Unit<Vector3<f64>>,
+    cos_sun_radius: f64,
+    sun_radiance: Vector3<f64>,
+    /// Perez coefficients A to E for luminance, then the x and y chromaticities.
+    perez: [[f64; 5]; 3],
+    /// Luminance and chromaticity straight up, each divided by the Perez
+    /// function there.
+    zenith: [f64; 3],
+    intensity: f64,
+}
+
+impl Sky {
+    /// A sky with the sun `elevation` degrees above the horizon, `azimuth`
+    /// degrees clockwise from -z (seen from above).
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Self {
+        let elevation = degrees_to_radians(elevation.clamp(0.0, 90.0));
+        let azimuth = degrees_to_radians(azimuth);
+        let sun = Unit::new_normalize(vector![
+            azimuth.sin() * elevation.cos(),
+            elevation.sin(),
+            -azimuth.cos() * elevation.cos()
+        ]);
+        let theta_s = PI / 2.0 - elevation;
+        let t = turbidity;
+
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let chromaticity = |m: [[f64; 4]; 3]| {
+            let (t2, s) = (t * t, theta_s);
+            let s = [s * s * s, s * s, s, 1.0];
+            let row = |r: [f64; 4]| r.iter().zip(&s).map(|(a, b)| a * b).sum::<f64>();
+            t2 * row(m[0]) + t * row(m[1]) + row(m[2])
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+        let zenith = [zenith_luminance, zenith_x, zenith_y];
+        let mut relative_zenith = [0.0; 3];
+        for i in 0..3 {
+            relative_zenith[i] = zenith[i] / perez_function(&perez[i], 1.0, theta_s.cos());
+        }
+
+        Self {
+            sun,
+            cos_sun_radius: SUN_RADIUS.cos(),
+            sun_radiance: sun_transmittance(theta_s, t) * SUN_LUMINANCE * SKY_SCALE,
+            perez,
+            zenith: relative_zenith,
+            intensity: 1.0,
+        }
+    }
+
+    /// Scales the sky and sun together.
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Scales the sun's angular size, keeping the light it gives constant, so
+    /// larger suns cast softer shadows.
+    pub fn with_sun_size(mut self, size: f64) -> Self {
+        let cos_sun_radius = (SUN_RADIUS * size).min(PI / 2.0).cos();
+        self.sun_radiance *= (1.0 - self.cos_sun_radius) / (1.0 - cos_sun_radius);
+        self.cos_sun_radius = cos_sun_radius;
+        self
+    }
+
+    pub fn colour(&self, direction: &Unit<Vector3<f64>>) -> Vector3<f64> {
+        let cos_gamma = direction.dot(&self.sun);
+        // Below the horizon the sky carries on at its horizon colour.
+        let cos_theta = direction.y.max(1e-3);
+        let [luminance, x, y] = [0, 1, 2]
+            .map(|i| self.zenith[i] * perez_function(&self.perez[i], cos_theta, cos_gamma));
+        let mut colour = xyy_to_rgb(luminance, x, y) * SKY_SCALE;
+        if cos_gamma >= self.cos_sun_radius {
+            colour += self.sun_radiance;
+        }
+        colour * self.intensity
+    }
+
+    /// Samples the sun disk uniformly in solid angle.
+    pub fn sample(&self, rng: &mut Random) -> Unit<Vector3<f64>> {
+        Onb::new(&self.sun).to_world(&sample_uniform_cone(rng.sample_2d(), self.cos_sun_radius))
+    }
+
+    pub fn pdf(&self, direction: &Unit<Vector3<f64>>) -> f64 {
+        if direction.dot(&self.sun) >= self.cos_sun_radius {
+            uniform_cone_pdf(self.cos_sun_radius)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Perez's sky distribution for a view `acos(cos_theta)` from the zenith and
+/// `acos(cos_gamma)` from the sun.
+fn perez_function(c: &[f64; 5], cos_theta: f64, cos_gamma: f64) -> f64 {
+    let gamma = cos_gamma.clamp(-1.0, 1.0).acos();
+    (1.0 + c[0] * (c[1] / cos_theta).exp())
+        * (1.0 + c[2] * (c[3] * gamma).exp() + c[4] * cos_gamma * cos_gamma)
+}
+
+/// Linear sRGB from CIE luminance `luminance` and chromaticity `(x, y)`.
+fn xyy_to_rgb(luminance: f64, x: f64, y: f64) -> Vector3<f64> {
+    let cie_x = x / y * luminance;
+    let cie_z = (1.0 - x - y) / y * luminance;
+    vector![
+        3.2406 * cie_x - 1.5372 * luminance - 0.4986 * cie_z,
+        -0.9689 * cie_x + 1.8758 * luminance + 0.0415 * cie_z,
+        0.0557 * cie_x - 0.2040 * luminance + 1.0570 * cie_z
+    ]
+    .map(|c| c.max(0.0))
+}
+
+/// Fraction of sunlight at the red, green and blue wavelengths that gets
+/// through the air when the sun is `theta_s` radians from the zenith, from
+/// Rayleigh scattering by the air and Mie scattering by haze.
+fn sun_transmittance(theta_s: f64, turbidity: f64) -> Vector3<f64> {
+    // Relative optical path length through the atmosphere (Kasten's formula).
+    let zenith_degrees = theta_s.to_degrees();
+    let air_mass = 1.0 / (theta_s.cos() + 0.15 * (93.885 - zenith_degrees).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+    vector![0.680, 0.550, 0.440].map(|wavelength: f64| {
+        let rayleigh = 0.008735 * wavelength.powf(-4.08);
+        let aerosol = beta * wavelength.powf(-1.3);
+        (-(rayleigh + aerosol) * air_mass).exp()
+    })
+}
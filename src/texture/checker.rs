@@ -0,0 +1,36 @@
+use nalgebra::Vector3;
+use serde::Deserialize;
+
+use super::{Texture, TextureKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckerMapping {
+    /// Squares laid out over the surface UVs.
+    Uv,
+    /// Cubes filling world space, independent of the surface parameterisation.
+    Solid,
+}
+
+#[derive(Debug, Clone)]
+pub struct Checker {
+    pub even: Box<TextureKind>,
+    pub odd: Box<TextureKind>,
+    /// Squares per unit of UV, or per world unit for `Solid`.
+    pub scale: f64,
+    pub mapping: CheckerMapping,
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, p: &Vector3<f64>) -> Vector3<f64> {
+        let cells = match self.mapping {
+            CheckerMapping::Uv => (u * self.scale).floor() + (v * self.scale).floor(),
+            CheckerMapping::Solid => (p * self.scale).map(f64::floor).sum(),
+        };
+        if cells.rem_euclid(2.0) < 1.0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::{fmt, path::Path, sync::Arc};
+
+use nalgebra::{vector, Vector3};
+
+use super::Texture;
+
+/// A bitmap sampled with bilinear filtering, repeating outside 0..1.
+#[derive(Clone)]
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    /// Linear RGB texels, top row first.
+    texels: Arc<Vec<Vector3<f64>>>,
+}
+
+impl fmt::Debug for ImageTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageTexture")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+fn srgb_to_linear(c: u8) -> f64 {
+    let c = c as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl ImageTexture {
+    /// Loads an 8-bit image, treating its contents as sRGB encoded.
+    pub fn open(path: impl AsRef<Path>) -> image::ImageResult<Self> {
+        let img = image::open(path)?.to_rgb8();
+        let texels = img
+            .pixels()
+            .map(|p| {
+                vector![
+                    srgb_to_linear(p[0]),
+                    srgb_to_linear(p[1]),
+                    srgb_to_linear(p[2])
+                ]
+            })
+            .collect();
+        Ok(Self {
+            width: img.width() as usize,
+            height: img.height() as usize,
+            texels: Arc::new(texels),
+        })
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vector3<f64> {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.rem_euclid(self.height as i64) as usize;
+        self.texels[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Vector3<f64>) -> Vector3<f64> {
+        // Texel centres sit at half-integer coordinates; v runs bottom to top.
+        let x = u * self.width as f64 - 0.5;
+        let y = (1.0 - v) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
@@ -0,0 +1,32 @@
+mod checker;
+mod image_texture;
+mod noise;
+mod solid;
+
+pub use checker::{Checker, CheckerMapping};
+use enum_dispatch::enum_dispatch;
+pub use image_texture::ImageTexture;
+use nalgebra::Vector3;
+pub use noise::{NoiseKind, NoiseTexture};
+pub use solid::SolidColour;
+
+#[enum_dispatch]
+pub trait Texture {
+    /// Colour at surface coordinates `(u, v)` and world-space point `p`.
+    fn value(&self, u: f64, v: f64, p: &Vector3<f64>) -> Vector3<f64>;
+}
+
+#[derive(Debug, Clone)]
+#[enum_dispatch(Texture)]
+pub enum TextureKind {
+    Solid(SolidColour),
+    Checker(Checker),
+    Image(ImageTexture),
+    Noise(NoiseTexture),
+}
+
+impl From<Vector3<f64>> for TextureKind {
+    fn from(colour: Vector3<f64>) -> Self {
+        TextureKind::Solid(SolidColour { colour })
+    }
+}
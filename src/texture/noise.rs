@@ -0,0 +1,133 @@
+use std::{fmt, sync::Arc};
+
+use nalgebra::{Unit, Vector3};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Deserialize;
+
+use super::Texture;
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise over a lattice of random unit vectors.
+pub struct Perlin {
+    gradients: Vec<Vector3<f64>>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    /// The lattice is generated from `seed`, so a given scene always gets
+    /// the same pattern.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let gradients = (0..POINT_COUNT)
+            .map(|_| {
+                Unit::new_normalize(Vector3::from_fn(|_, _| rng.gen_range(-1.0..1.0))).into_inner()
+            })
+            .collect();
+        let permutation = |rng: &mut StdRng| {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            p.shuffle(rng);
+            p
+        };
+        Self {
+            gradients,
+            perm_x: permutation(&mut rng),
+            perm_y: permutation(&mut rng),
+            perm_z: permutation(&mut rng),
+        }
+    }
+
+    /// Smoothly interpolated noise in roughly -1..1.
+    pub fn noise(&self, p: &Vector3<f64>) -> f64 {
+        let floor = p.map(f64::floor);
+        let f = p - floor;
+        // Hermite smoothing removes the grid artefacts of linear blending.
+        let w = f.map(|t| t * t * (3.0 - 2.0 * t));
+        let (i, j, k) = (floor[0] as i64, floor[1] as i64, floor[2] as i64);
+
+        let mut accum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let index = self.perm_x[((i + di) & 255) as usize]
+                        ^ self.perm_y[((j + dj) & 255) as usize]
+                        ^ self.perm_z[((k + dk) & 255) as usize];
+                    let (fi, fj, fk) = (di as f64, dj as f64, dk as f64);
+                    let weight = Vector3::new(f[0] - fi, f[1] - fj, f[2] - fk);
+                    accum += (fi * w[0] + (1.0 - fi) * (1.0 - w[0]))
+                        * (fj * w[1] + (1.0 - fj) * (1.0 - w[1]))
+                        * (fk * w[2] + (1.0 - fk) * (1.0 - w[2]))
+                        * self.gradients[index].dot(&weight);
+                }
+            }
+        }
+        accum
+    }
+
+    /// Sum of `depth` octaves of absolute noise.
+    pub fn turbulence(&self, p: &Vector3<f64>, depth: usize) -> f64 {
+        let mut accum = 0.0;
+        let mut p = *p;
+        let mut weight = 1.0;
+        for _ in 0..depth {
+            accum += weight * self.noise(&p);
+            weight *= 0.5;
+            p *= 2.0;
+        }
+        accum.abs()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseKind {
+    /// Plain Perlin noise.
+    Perlin,
+    /// Several octaves of noise, for a cloudy look.
+    Turbulence,
+    /// Sine bands along z disturbed by turbulence.
+    Marble,
+}
+
+#[derive(Clone)]
+pub struct NoiseTexture {
+    perlin: Arc<Perlin>,
+    pub colour: Vector3<f64>,
+    pub scale: f64,
+    pub kind: NoiseKind,
+}
+
+impl fmt::Debug for NoiseTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NoiseTexture")
+            .field("colour", &self.colour)
+            .field("scale", &self.scale)
+            .field("kind", &self.kind)
+            .finish()
+    }
+}
+
+impl NoiseTexture {
+    pub fn new(kind: NoiseKind, colour: Vector3<f64>, scale: f64, seed: u64) -> Self {
+        Self {
+            perlin: Arc::new(Perlin::new(seed)),
+            colour,
+            scale,
+            kind,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Vector3<f64>) -> Vector3<f64> {
+        let s = self.scale * p;
+        let t = match self.kind {
+            NoiseKind::Perlin => 0.5 * (1.0 + self.perlin.noise(&s)),
+            NoiseKind::Turbulence => self.perlin.turbulence(&s, 7),
+            NoiseKind::Marble => 0.5 * (1.0 + (s[2] + 10.0 * self.perlin.turbulence(p, 7)).sin()),
+        };
+        self.colour * t
+    }
+}
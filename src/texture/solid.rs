@@ -0,0 +1,14 @@
+use nalgebra::Vector3;
+
+use super::Texture;
+
+#[derive(Debug, Clone)]
+pub struct SolidColour {
+    pub colour: Vector3<f64>,
+}
+
+impl Texture for SolidColour {
+    fn value(&self, _u: f64, _v: f64, _p: &Vector3<f64>) -> Vector3<f64> {
+        self.colour
+    }
+}
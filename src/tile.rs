@@ -0,0 +1,144 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use serde::Deserialize;
+
+/// How the renderer spends its samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RenderMode {
+    /// One sample for every pixel per pass, so the whole frame refines together.
+    #[default]
+    Progressive,
+    /// Square tiles rendered to their full sample count one after another, so
+    /// finished parts of the image appear early and each worker stays within
+    /// a small, cache friendly region.
+    Tiled {
+        #[serde(default = "default_tile_size")]
+        size: u64,
+        #[serde(default)]
+        order: TileOrder,
+    },
+}
+
+pub const DEFAULT_TILE_SIZE: u64 = 32;
+
+fn default_tile_size() -> u64 {
+    DEFAULT_TILE_SIZE
+}
+
+/// The order tiles are handed to workers in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TileOrder {
+    /// Rows from the top of the image down, left to right.
+    Scanline,
+    /// Outwards from the centre of the image, where the subject usually is.
+    #[default]
+    Spiral,
+    /// Along a Hilbert curve, keeping consecutive tiles next to each other.
+    Hilbert,
+}
+
+impl FromStr for TileOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scanline" => Ok(TileOrder::Scanline),
+            "spiral" => Ok(TileOrder::Spiral),
+            "hilbert" => Ok(TileOrder::Hilbert),
+            _ => Err(format!(
+                "unknown tile order '{}' (expected scanline, spiral or hilbert)",
+                s
+            )),
+        }
+    }
+}
+
+/// A rectangle of pixels, in the renderer's bottom-up pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u64,
+    pub y: u64,
+    pub width: u64,
+    pub height: u64,
+}
+
+impl Tile {
+    pub fn pixel_count(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    /// Buffer indices of the tile's pixels in an image `image_width` wide, row by row.
+    pub fn pixels(&self, image_width: u64) -> impl Iterator<Item = usize> + '_ {
+        (self.y..self.y + self.height).flat_map(move |y| {
+            (self.x..self.x + self.width).map(move |x| (y * image_width + x) as usize)
+        })
+    }
+}
+
+/// Splits a `width` by `height` image into tiles of at most `size` pixels a
+/// side, sorted into `order`.
+pub fn tiles(width: u64, height: u64, size: u64, order: TileOrder) -> Vec<Tile> {
+    let size = size.max(1);
+    let columns = width.div_ceil(size);
+    let rows = height.div_ceil(size);
+
+    let mut grid: Vec<(u64, u64)> = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .collect();
+    match order {
+        // Rows count up from the bottom of the image, so go through them backwards.
+        TileOrder::Scanline => grid.sort_by_key(|&(column, row)| (rows - 1 - row, column)),
+        TileOrder::Spiral => {
+            let centre = ((columns as f64 - 1.0) / 2.0, (rows as f64 - 1.0) / 2.0);
+            let key = |&(column, row): &(u64, u64)| {
+                let dx = column as f64 - centre.0;
+                let dy = row as f64 - centre.1;
+                (dx.abs().max(dy.abs()), dy.atan2(dx))
+            };
+            grid.sort_by(|a, b| {
+                let (a, b) = (key(a), key(b));
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            });
+        }
+        TileOrder::Hilbert => {
+            let side = columns.max(rows).next_power_of_two();
+            grid.sort_by_key(|&(column, row)| hilbert_index(side, column, row));
+        }
+    }
+
+    grid.into_iter()
+        .map(|(column, row)| {
+            let (x, y) = (column * size, row * size);
+            Tile {
+                x,
+                y,
+                width: size.min(width - x),
+                height: size.min(height - y),
+            }
+        })
+        .collect()
+}
+
+/// Distance of `(x, y)` along the Hilbert curve filling a `side` by `side`
+/// grid, where `side` is a power of two.
+fn hilbert_index(side: u64, mut x: u64, mut y: u64) -> u64 {
+    let mut index = 0;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = u64::from(x & s > 0);
+        let ry = u64::from(y & s > 0);
+        index += s * s * ((3 * rx) ^ ry);
+        // Rotate the quadrant so the curve inside it lines up with the next level.
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
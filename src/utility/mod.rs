@@ -1,11 +1,19 @@
+mod sampling;
 mod vec;
 
 use std::f64::consts::PI;
 
+pub use sampling::*;
 pub use vec::*;
 
-use nalgebra::{vector, Unit, Vector3};
-use rand::{distributions::Uniform, prelude::ThreadRng, thread_rng, Rng};
+use nalgebra::{vector, Unit, Vector2, Vector3};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+use crate::sampler::{
+    bounce_dimension, hash_all, PixelSample, Sampler, SamplerKind, BOUNCE_DIMENSIONS,
+    CAMERA_DIMENSIONS,
+};
 
 pub fn clamp<T: PartialOrd>(x: T, min: T, max: T) -> T {
     if x < min {
@@ -21,35 +29,79 @@ pub fn degrees_to_radians(d: f64) -> f64 {
     d * PI / 180.0
 }
 
+/// Random numbers for one camera sample. Values from `sample_1d` and
+/// `sample_2d` come from the render's low-discrepancy sampler, one dimension
+/// after another; `random_range` uses a plain generator. Both are seeded
+/// from the pixel and sample number, so a render depends only on its seed
+/// and not on how the work was split between threads.
 pub struct Random {
-    rng: ThreadRng,
+    rng: Pcg32,
+    sampler: SamplerKind,
+    sample: PixelSample,
+    dimension: u32,
+    /// End of the block of dimensions set aside for the current camera
+    /// sample or bounce, which draws must not run past.
+    block_end: u32,
 }
 
 impl Random {
-    pub fn new() -> Self {
-        Random { rng: thread_rng() }
+    pub fn for_sample(sampler: SamplerKind, sample: PixelSample) -> Self {
+        Random {
+            rng: Pcg32::seed_from_u64(hash_all(&[sample.seed, sample.pixel, sample.index])),
+            sampler,
+            sample,
+            dimension: 0,
+            block_end: CAMERA_DIMENSIONS,
+        }
+    }
+
+    /// Moves on to the dimensions set aside for bounce number `bounce`.
+    pub fn start_bounce(&mut self, bounce: u64) {
+        self.dimension = bounce_dimension(bounce);
+        self.block_end = self.dimension.saturating_add(BOUNCE_DIMENSIONS);
     }
 
-    pub fn random_vec(&mut self, min: f64, max: f64) -> Vector3<f64> {
-        let range = Uniform::new(min, max);
-        vector![
-            self.rng.sample(range),
-            self.rng.sample(range),
-            self.rng.sample(range)
-        ]
+    /// The next sampler dimension, in `[0, 1)`.
+    pub fn sample_1d(&mut self) -> f64 {
+        debug_assert!(
+            self.dimension < self.block_end,
+            "sampler dimension {} is past the end of its block",
+            self.dimension
+        );
+        let x = self.sampler.sample(&self.sample, self.dimension);
+        self.dimension = self.dimension.saturating_add(1);
+        x
     }
 
+    /// The next two sampler dimensions, in `[0, 1)`.
+    pub fn sample_2d(&mut self) -> Vector2<f64> {
+        let x = self.sample_1d();
+        vector![x, self.sample_1d()]
+    }
+
+    pub fn random_range(&mut self, min: f64, max: f64) -> f64 {
+        self.rng.gen_range(min..max)
+    }
+
+    /// A uniformly chosen index in `0..len`, from one sampler dimension.
+    pub fn random_index(&mut self, len: usize) -> usize {
+        ((self.sample_1d() * len as f64) as usize).min(len - 1)
+    }
+
+    /// A uniformly distributed point in the unit ball, from three sampler
+    /// dimensions.
     pub fn random_in_unit_sphere(&mut self) -> Vector3<f64> {
-        loop {
-            let p = self.random_vec(-1.0, 1.0);
-            if p.dot(&p) >= 1.0 {
-                continue;
-            }
-            return p;
-        }
+        let direction = self.random_unit_vec();
+        direction.into_inner() * self.sample_1d().cbrt()
     }
 
+    /// A uniformly distributed direction, from two sampler dimensions.
     pub fn random_unit_vec(&mut self) -> Unit<Vector3<f64>> {
-        Unit::new_normalize(self.random_in_unit_sphere())
+        sample_uniform_sphere(self.sample_2d())
+    }
+
+    /// A uniformly distributed point in the unit disk, from two sampler dimensions.
+    pub fn random_in_unit_disk(&mut self) -> Vector2<f64> {
+        sample_uniform_disk(self.sample_2d())
     }
 }
@@ -0,0 +1,54 @@
+//! Closed-form warps from points in the unit square to common distributions
+//! of directions and points, each with the density it samples. Directions
+//! are in a local frame with +z as the axis; an [`Onb`](super::Onb) carries
+//! them into world space.
+
+use std::f64::consts::PI;
+
+use nalgebra::{vector, Unit, Vector2, Vector3};
+
+/// A point in the unit disk, uniform in area.
+pub fn sample_uniform_disk(u: Vector2<f64>) -> Vector2<f64> {
+    let r = u[0].sqrt();
+    let theta = 2.0 * PI * u[1];
+    vector![r * theta.cos(), r * theta.sin()]
+}
+
+pub fn uniform_disk_pdf() -> f64 {
+    1.0 / PI
+}
+
+/// A direction over the whole sphere, uniform in solid angle.
+pub fn sample_uniform_sphere(u: Vector2<f64>) -> Unit<Vector3<f64>> {
+    let z = 1.0 - 2.0 * u[0];
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u[1];
+    Unit::new_unchecked(vector![r * phi.cos(), r * phi.sin(), z])
+}
+
+pub fn uniform_sphere_pdf() -> f64 {
+    1.0 / (4.0 * PI)
+}
+
+/// A direction in the +z hemisphere with density proportional to its
+/// cosine, by projecting a uniform disk sample up onto the hemisphere.
+pub fn sample_cosine_hemisphere(u: Vector2<f64>) -> Vector3<f64> {
+    let d = sample_uniform_disk(u);
+    vector![d.x, d.y, (1.0 - d.norm_squared()).max(0.0).sqrt()]
+}
+
+pub fn cosine_hemisphere_pdf(cos_theta: f64) -> f64 {
+    cos_theta.max(0.0) * uniform_disk_pdf()
+}
+
+/// A direction within `acos(cos_theta_max)` of +z, uniform in solid angle.
+pub fn sample_uniform_cone(u: Vector2<f64>, cos_theta_max: f64) -> Vector3<f64> {
+    let z = 1.0 + u[1] * (cos_theta_max - 1.0);
+    let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u[0];
+    vector![phi.cos() * sin_theta, phi.sin() * sin_theta, z]
+}
+
+pub fn uniform_cone_pdf(cos_theta_max: f64) -> f64 {
+    1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+}
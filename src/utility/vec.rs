@@ -1,6 +1,6 @@
 use std::ops::Mul;
 
-use nalgebra::{Unit, Vector3};
+use nalgebra::{vector, Unit, Vector3};
 
 pub trait NamedField<T: Copy> {
     fn x(&self) -> T;
@@ -32,12 +32,6 @@ impl<T: Copy> NamedField<T> for Unit<Vector3<T>> {
     }
 }
 
-pub fn near_zero(v: &Vector3<f64>) -> bool {
-    let s = 1e-8;
-
-    v.x().abs() < s && v.y().abs() < s && v.z().abs() < s
-}
-
 pub fn reflect(v: Vector3<f64>, n: Unit<Vector3<f64>>) -> Vector3<f64> {
     v - n.mul(2.0 * v.dot(&n))
 }
@@ -48,3 +42,39 @@ pub fn refract(uv: Vector3<f64>, n: Unit<Vector3<f64>>, rri: f64) -> Vector3<f64
     let r_out_parallel = n.mul(-((1.0 - r_out_perp.norm_squared()).abs().sqrt()));
     r_out_perp + r_out_parallel
 }
+
+/// An orthonormal basis around an axis `w`, for moving directions between
+/// world space and a local frame in which `w` is +z. The `u` axis follows
+/// lines of latitude about the world y axis, so anisotropic highlights on a
+/// sphere stretch around it like brushed metal.
+#[derive(Debug, Clone)]
+pub struct Onb {
+    pub u: Vector3<f64>,
+    pub v: Vector3<f64>,
+    pub w: Vector3<f64>,
+}
+
+impl Onb {
+    pub fn new(w: &Unit<Vector3<f64>>) -> Self {
+        let axis = if w.y.abs() > 0.999 {
+            Vector3::x()
+        } else {
+            Vector3::y()
+        };
+        let u = axis.cross(w).normalize();
+        let v = w.cross(&u);
+        Self {
+            u,
+            v,
+            w: w.into_inner(),
+        }
+    }
+
+    pub fn to_local(&self, a: &Vector3<f64>) -> Vector3<f64> {
+        vector![a.dot(&self.u), a.dot(&self.v), a.dot(&self.w)]
+    }
+
+    pub fn to_world(&self, a: &Vector3<f64>) -> Unit<Vector3<f64>> {
+        Unit::new_normalize(self.u * a.x + self.v * a.y + self.w * a.z)
+    }
+}